@@ -0,0 +1,91 @@
+const WORD_BITS : usize = 64;
+
+//one bit per cell, each row starting on a fresh word so an empty row can be skipped a word at a time
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BitGrid {
+    words : Vec<u64>,
+    width : usize,
+    height : usize,
+}
+
+impl BitGrid {
+    pub fn new(width : usize) -> BitGrid {
+        BitGrid { words: vec![], width, height: 0 }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    fn words_per_row(&self) -> usize {
+        self.width.div_ceil(WORD_BITS)
+    }
+
+    //appends a row of cleared bits and returns its index
+    pub fn push_row(&mut self) -> usize {
+        self.words.resize(self.words.len() + self.words_per_row(), 0);
+        self.height += 1;
+        self.height - 1
+    }
+
+    pub fn get(&self, x : usize, y : usize) -> bool {
+        assert!(x < self.width && y < self.height, "{},{} is outside of the grid", x, y);
+        self.row_words(y)[x / WORD_BITS] >> (x % WORD_BITS) & 1 == 1
+    }
+
+    pub fn set(&mut self, x : usize, y : usize, value : bool) {
+        assert!(x < self.width && y < self.height, "{},{} is outside of the grid", x, y);
+        let index = y * self.words_per_row() + x / WORD_BITS;
+        let word = &mut self.words[index];
+        let bit = 1 << (x % WORD_BITS);
+        if value {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+
+    //bits past the width of the row are always clear
+    pub fn row_words(&self, y : usize) -> &[u64] {
+        let words_per_row = self.words_per_row();
+        &self.words[y * words_per_row..(y + 1) * words_per_row]
+    }
+
+    #[cfg(test)]
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    //bytes used by the bits themselves
+    #[cfg(test)]
+    pub fn memory(&self) -> usize {
+        self.words.len() * std::mem::size_of::<u64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_get() {
+        let mut grid = BitGrid::new(70);
+        assert_eq!(grid.push_row(), 0);
+        assert_eq!(grid.push_row(), 1);
+        grid.set(0, 0, true);
+        grid.set(69, 0, true);
+        grid.set(64, 1, true);
+        grid.set(3, 1, true);
+        grid.set(3, 1, false);
+        assert!(grid.get(0, 0) && grid.get(69, 0) && grid.get(64, 1));
+        assert!(!grid.get(1, 0) && !grid.get(3, 1) && !grid.get(69, 1));
+        assert_eq!(grid.row_words(0), &[1, 1 << 5]);
+        assert_eq!(grid.row_words(1), &[0, 1]);
+        assert_eq!(grid.count_ones(), 3);
+        assert_eq!((grid.width(), grid.height(), grid.memory()), (70, 2, 32));
+    }
+}
@@ -0,0 +1,94 @@
+use std::{error::Error, fmt};
+
+//quotes a field only when it holds a comma, a quote or a line break
+pub fn field(s : &str) -> String {
+    if s.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+pub fn row(fields : &[&str]) -> String {
+    fields.iter().map(|f| field(f)).collect::<Vec<String>>().join(",")
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CsvParseError {
+    pub line : usize,
+    pub message : String,
+}
+
+impl Error for CsvParseError {}
+impl fmt::Display for CsvParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse CSV at line {}: {}", self.line, self.message)
+    }
+}
+
+//every row with the line it starts on, quoted fields may span lines, empty lines are skipped
+pub fn parse(input : &str) -> Result<Vec<(usize, Vec<String>)>, CsvParseError> {
+    let mut rows = vec![];
+    let mut fields : Vec<String> = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                current.push('"');
+            },
+            '"' if quoted => quoted = false,
+            '"' if current.is_empty() => quoted = true,
+            '"' => return Err(CsvParseError { line, message: "quote in the middle of an unquoted field".to_string() }),
+            '\n' if quoted => {
+                line += 1;
+                current.push(c);
+            },
+            ',' if !quoted => fields.push(std::mem::take(&mut current)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {},
+            '\n' => {
+                if !fields.is_empty() || !current.is_empty() {
+                    fields.push(std::mem::take(&mut current));
+                    rows.push((start, std::mem::take(&mut fields)));
+                }
+                line += 1;
+                start = line;
+            },
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return Err(CsvParseError { line: start, message: "unterminated quoted field".to_string() });
+    }
+    if !fields.is_empty() || !current.is_empty() {
+        fields.push(current);
+        rows.push((start, fields));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read_rows() {
+        let written = [row(&["a", "b,c", ""]), row(&["say \"hi\"", "two\nlines", "x"])].join("\r\n");
+        assert_eq!(written, "a,\"b,c\",\r\n\"say \"\"hi\"\"\",\"two\nlines\",x");
+        assert_eq!(parse(written.as_str()).unwrap(), vec![
+            (1, vec!["a".to_string(), "b,c".to_string(), "".to_string()]),
+            (2, vec!["say \"hi\"".to_string(), "two\nlines".to_string(), "x".to_string()]),
+        ]);
+        assert_eq!(parse("a\n\nb\n").unwrap(), vec![(1, vec!["a".to_string()]), (3, vec!["b".to_string()])]);
+    }
+
+    #[test]
+    fn csv_errors() {
+        assert_eq!(parse("a\n\"b").unwrap_err().to_string(), "Unable to parse CSV at line 2: unterminated quoted field");
+        assert_eq!(parse("a\"b").unwrap_err().to_string(), "Unable to parse CSV at line 1: quote in the middle of an unquoted field");
+    }
+}
@@ -1,6 +1,24 @@
 use crate::inputs::read_input;
+use crate::options::Options;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::{fmt, io};
 
-#[derive(Clone, Debug, PartialEq)]
+#[cfg(test)]
+use rstest::rstest;
+
+mod audit;
+mod generator;
+mod parser;
+mod rules;
+mod stream;
+
+use audit::AuditReport;
+use generator::{Outcome, PasswordGenerator};
+use parser::PolicyParseError;
+use rules::{Rule, RuleFailure};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 struct PasswordPolicy {
     position1 : usize,
     position2 : usize,
@@ -8,61 +26,177 @@ struct PasswordPolicy {
 }
 
 impl PasswordPolicy {
-    fn from_str(s : &str) -> PasswordPolicy {
-        let tokens : Vec<&str> = s.split(" ").collect();
-        let range: Vec<usize> = tokens[0].split("-")
-            .map(|x| x.to_string().parse::<usize>().unwrap())
-            .collect();
-        PasswordPolicy {
-            position1: range[0],
-            position2: range[1],
-            character: tokens[1].chars().next().unwrap(),
+    //fast path for the `N-M c: password` form, returns None for anything else
+    fn parse_line(s : &str) -> Option<(PasswordPolicy, &str)> {
+        fn number(bytes : &[u8], i : &mut usize) -> Option<usize> {
+            let start = *i;
+            let mut value : usize = 0;
+            while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+                value = value.checked_mul(10)?.checked_add((bytes[*i] - b'0') as usize)?;
+                *i += 1;
+            }
+            if *i == start { None } else { Some(value) }
+        }
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        let position1 = number(bytes, &mut i)?;
+        if bytes.get(i) != Some(&b'-') {
+            return None;
         }
+        i += 1;
+        let position2 = number(bytes, &mut i)?;
+        if bytes.get(i) != Some(&b' ') {
+            return None;
+        }
+        let character = s[i + 1..].chars().next()?;
+        i += 1 + character.len_utf8();
+        if bytes.get(i) != Some(&b':') || character.is_whitespace() {
+            return None;
+        }
+        Some((PasswordPolicy { position1, position2, character }, s[i + 1..].trim()))
     }
 
-    fn is_valid(self, password : String) -> bool {
-        let chars : Vec<char> = password.chars().collect();
-        let position1 = self.position1 - 1;
-        let position2 = self.position2 - 1;
-        if chars[position1] == self.character && chars[position2] == self.character {
-            return false;
-        } else if chars[position1] == self.character || chars[position2] == self.character {
-            return true;
+    fn character_at(&self, password : &str, position : usize) -> Option<bool> {
+        if position == 0 {
+            return None;
+        }
+        if password.is_ascii() {
+            password.as_bytes().get(position - 1)
+                .map(|b| self.character.is_ascii() && *b == self.character as u8)
         } else {
-            return false;
+            password.chars().nth(position - 1).map(|c| c == self.character)
+        }
+    }
+
+    fn is_valid(&self, password : &str) -> bool {
+        match (self.character_at(password, self.position1), self.character_at(password, self.position2)) {
+            (Some(first), Some(second)) => first != second,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for PasswordPolicy {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{} {}", self.position1, self.position2, self.character)
+    }
+}
+
+impl From<PasswordPolicy> for Rule {
+    fn from(policy : PasswordPolicy) -> Rule {
+        Rule::Positional {
+            position1: policy.position1,
+            position2: policy.position2,
+            characters: policy.character.to_string(),
         }
     }
 }
 
 #[derive(Clone, Debug, PartialEq)]
-struct Password {
-    password: String,
-    policy: PasswordPolicy
+enum Policy {
+    Positional(PasswordPolicy),
+    Rule(Rule),
+}
+
+impl From<PasswordPolicy> for Policy {
+    fn from(policy : PasswordPolicy) -> Policy {
+        Policy::Positional(policy)
+    }
 }
-impl Password {
-    fn from_str(s : &str) -> Password {
-        let tokens : Vec<&str> = s.split(":").collect();
-        Password {
-            policy: PasswordPolicy::from_str(tokens[0]),
-            password: tokens[1].trim().to_string(),
+
+#[derive(Clone, Debug, PartialEq)]
+struct Password<'a> {
+    password: &'a str,
+    policy: Policy
+}
+
+impl<'a> Password<'a> {
+    fn parse(s : &'a str) -> Result<Password<'a>, PolicyParseError> {
+        if let Some((policy, password)) = PasswordPolicy::parse_line(s) {
+            return Ok(Password {
+                policy: Policy::Positional(policy),
+                password,
+            });
         }
+        let (policy, password) = parser::parse_line(s)?;
+        Ok(Password {
+            policy: Policy::Rule(policy),
+            password,
+        })
     }
 
-    fn is_valid(self) -> bool {
-        self.policy.is_valid(self.password)
+    fn check(&self) -> Result<(), RuleFailure> {
+        match &self.policy {
+            Policy::Positional(policy) => {
+                if policy.is_valid(self.password) {
+                    Ok(())
+                } else {
+                    Rule::from(*policy).evaluate(self.password)
+                }
+            },
+            Policy::Rule(rule) => rule.evaluate(self.password),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match &self.policy {
+            Policy::Positional(policy) => policy.is_valid(self.password),
+            Policy::Rule(rule) => rule.evaluate(self.password).is_ok(),
+        }
     }
 }
 
-pub fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let contents = read_input(2)?;
+fn generate(options : &Options, lines : usize) -> Result<(), Box<dyn std::error::Error>> {
+    let seed = options.parse::<u64>("seed")?.unwrap_or(2020);
+    let outcomes : Result<Vec<Outcome>, _> = options.get("outcome")
+        .map(|x| x.split(',').map(str::parse::<Outcome>).collect())
+        .unwrap_or_else(|| Ok(vec![]));
+    let mut generator = PasswordGenerator::new(seed);
+    match options.get("output") {
+        Some(path) => {
+            println!("writing {} generated lines with seed {} to {:?}", lines, seed, path);
+            let mut writer = BufWriter::new(File::create(path)?);
+            generator.write_lines(&mut writer, lines, &outcomes?)?;
+            writer.flush()?;
+        },
+        None => generator.write_lines(&mut io::stdout().lock(), lines, &outcomes?)?,
+    }
+    Ok(())
+}
 
-    let lines : Vec<Password> = contents.split("\n")
-        .filter(|x| !x.is_empty())
-        .map(Password::from_str)
-        .filter(|x| x.clone().is_valid())
-        .collect();
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::from_env();
+    if let Some(lines) = options.parse::<usize>("generate")? {
+        return generate(&options, lines);
+    }
+    let threads = options.parse::<usize>("threads")?.unwrap_or(1);
+    let contents;
+    let reader : Box<dyn BufRead> = match options.get("input") {
+        Some(path) => {
+            println!("streaming file {:?} with {} thread(s)", path, threads);
+            Box::new(BufReader::with_capacity(stream::CHUNK_SIZE, File::open(path)?))
+        },
+        None => {
+            contents = read_input(2)?;
+            Box::new(contents.as_bytes())
+        },
+    };
 
-    println!("found {} valid lines", lines.len());
+    match options.get("report") {
+        Some(format) => {
+            let max_offenders = options.parse::<usize>("offenders")?.unwrap_or(10);
+            let report = stream::process_reader(reader, threads, stream::CHUNK_SIZE, || AuditReport::new(max_offenders))?;
+            if format == "json" {
+                println!("{}", report.to_json());
+            } else {
+                println!("{}", report);
+            }
+        },
+        None => {
+            let summary = stream::validate_reader(reader, threads)?;
+            println!("found {} valid lines", summary.valid);
+        },
+    }
     Ok(())
 }
 
@@ -72,49 +206,97 @@ mod tests {
 
     #[test]
     fn parse_password_policy() {
-        assert_eq!(Password::from_str("1-3 a: abcde"), Password {
-            password: "abcde".to_string(),
+        assert_eq!(Password::parse("1-3 a: abcde").unwrap(), Password {
+            password: "abcde",
             policy: PasswordPolicy {
                 position1: 1,
                 position2: 3,
                 character: 'a'
-            }
+            }.into()
         })
     }
 
     #[test]
     fn validate_valid_password_policy() {
         let password = Password {
-            password: "abcde".to_string(),
+            password: "abcde",
             policy: PasswordPolicy {
                 position1: 1,
                 position2: 3,
                 character: 'a'
-            }
+            }.into()
         };
-        assert_eq!(password.is_valid(), true)
+        assert!(password.is_valid())
     }
 
     #[test]
     fn validate_invalid_password_policy() {
         let password1 = Password {
-            password: "cdefg".to_string(),
+            password: "cdefg",
             policy: PasswordPolicy {
                 position1: 1,
                 position2: 3,
                 character: 'b'
-            }
+            }.into()
         };
-        assert_eq!(password1.is_valid(), false);
+        assert!(!password1.is_valid());
 
         let password2 = Password {
-            password: "ccccccccc".to_string(),
+            password: "ccccccccc",
             policy: PasswordPolicy {
                 position1: 1,
                 position2: 3,
                 character: 'b'
-            }
+            }.into()
         };
-        assert_eq!(password2.is_valid(), false)
+        assert!(!password2.is_valid())
+    }
+
+    #[test]
+    fn mixed_policy_kinds() {
+        let passwords : Vec<Password> = "1-3 a: abcde
+count 1-3 b: cdefg
+length 4-8 and requires digit: hunter2
+not forbids \"123\": abc123"
+            .split("\n")
+            .map(|x| Password::parse(x).unwrap())
+            .collect();
+        let valid : Vec<bool> = passwords.iter().map(Password::is_valid).collect();
+        assert_eq!(valid, vec![true, false, true, true]);
+    }
+
+    #[test]
+    fn invalid_policy_is_an_error() {
+        let error = Password::parse("1-x a: abcde").unwrap_err();
+        assert_eq!(error.to_string(), "Unable to parse policy at column 3: expected a number");
+    }
+
+    #[rstest(line, policy, password,
+        case("1-3 a: abcde", Some(PasswordPolicy { position1: 1, position2: 3, character: 'a' }), "abcde"),
+        case("13-14 f:ffffffffnfffvv", Some(PasswordPolicy { position1: 13, position2: 14, character: 'f' }), "ffffffffnfffvv"),
+        case("1-2 é: éa", Some(PasswordPolicy { position1: 1, position2: 2, character: 'é' }), "éa"),
+        case("count 1-3 a: abcde", None, ""),
+        case("1-3 ab: abcde", None, ""),
+        case("1-3 a and length 1-5: abcde", None, ""),
+        case("99999999999999999999-1 a: abcde", None, ""),
+        ::trace
+    )]
+    fn parse_positional_fast_path(line : &str, policy : Option<PasswordPolicy>, password : &str) {
+        assert_eq!(PasswordPolicy::parse_line(line), policy.map(|policy| (policy, password)));
+    }
+
+    #[test]
+    fn fast_path_agrees_with_rule() {
+        let policy = PasswordPolicy { position1: 2, position2: 5, character: 'é' };
+        for password in &["aébcé", "aébcd", "abcdé", "abcde", "aé", "", "éééééé", "xéxxéx"] {
+            assert_eq!(policy.is_valid(password), Rule::from(policy).evaluate(password).is_ok(), "{}", password);
+        }
+    }
+
+    #[test]
+    fn out_of_range_is_invalid() {
+        let password = Password::parse("2-9 a: xa").unwrap();
+        assert!(!password.is_valid());
+        assert_eq!(password.check(), Err(RuleFailure::PositionOutOfRange { position: 9, length: 2 }));
     }
 }
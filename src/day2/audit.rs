@@ -0,0 +1,227 @@
+use super::stream::LineSink;
+use super::{Password, Policy};
+use super::rules::Rule;
+use crate::json;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fmt::Write;
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Counts {
+    pub valid : usize,
+    pub invalid : usize,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Offender {
+    pub line : usize,
+    pub text : String,
+    pub reason : String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct AuditReport {
+    pub counts : Counts,
+    pub by_character : BTreeMap<String, Counts>,
+    pub failures : BTreeMap<&'static str, usize>,
+    pub lengths : BTreeMap<usize, usize>,
+    pub offenders : Vec<Offender>,
+    max_offenders : usize,
+}
+
+//policies without characters of their own (length, classes, combinations...) are grouped under this key
+const NO_CHARACTER : &str = "*";
+
+impl AuditReport {
+    pub fn new(max_offenders : usize) -> AuditReport {
+        AuditReport {
+            counts: Counts::default(),
+            by_character: BTreeMap::new(),
+            failures: BTreeMap::new(),
+            lengths: BTreeMap::new(),
+            offenders: vec![],
+            max_offenders,
+        }
+    }
+
+    fn character_counts(&mut self, key : &str) -> &mut Counts {
+        if !self.by_character.contains_key(key) {
+            self.by_character.insert(key.to_string(), Counts::default());
+        }
+        self.by_character.get_mut(key).unwrap()
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        let _ = write!(out, "{{\"lines\":{},\"valid\":{},\"invalid\":{},\"by_character\":{{",
+            self.counts.valid + self.counts.invalid, self.counts.valid, self.counts.invalid);
+        for (i, (key, counts)) in self.by_character.iter().enumerate() {
+            let _ = write!(out, "{}{}:{{\"valid\":{},\"invalid\":{}}}",
+                if i > 0 { "," } else { "" }, json::string(key), counts.valid, counts.invalid);
+        }
+        out.push_str("},\"failures\":{");
+        for (i, (reason, count)) in self.failures.iter().enumerate() {
+            let _ = write!(out, "{}{}:{}", if i > 0 { "," } else { "" }, json::string(reason), count);
+        }
+        out.push_str("},\"lengths\":{");
+        for (i, (length, count)) in self.lengths.iter().enumerate() {
+            let _ = write!(out, "{}\"{}\":{}", if i > 0 { "," } else { "" }, length, count);
+        }
+        out.push_str("},\"offenders\":[");
+        for (i, offender) in self.offenders.iter().enumerate() {
+            let _ = write!(out, "{}{{\"line\":{},\"text\":{},\"reason\":{}}}",
+                if i > 0 { "," } else { "" }, offender.line, json::string(&offender.text), json::string(&offender.reason));
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+impl LineSink for AuditReport {
+    fn record(&mut self, line_number : usize, line : &str, password : &Password) {
+        let mut buffer = [0; 4];
+        let key : &str = match &password.policy {
+            Policy::Positional(policy) => policy.character.encode_utf8(&mut buffer),
+            Policy::Rule(Rule::Positional { characters, .. }) => characters,
+            Policy::Rule(Rule::Count { characters, .. }) => characters,
+            Policy::Rule(_) => NO_CHARACTER,
+        };
+        *self.lengths.entry(password.password.chars().count()).or_insert(0) += 1;
+        match password.check() {
+            Ok(()) => {
+                self.counts.valid += 1;
+                self.character_counts(key).valid += 1;
+            },
+            Err(failure) => {
+                self.counts.invalid += 1;
+                self.character_counts(key).invalid += 1;
+                *self.failures.entry(failure.kind()).or_insert(0) += 1;
+                if self.offenders.len() < self.max_offenders {
+                    self.offenders.push(Offender {
+                        line: line_number,
+                        text: line.to_string(),
+                        reason: failure.to_string(),
+                    });
+                }
+            },
+        }
+    }
+
+    fn merge(mut self, other : AuditReport) -> AuditReport {
+        self.counts.valid += other.counts.valid;
+        self.counts.invalid += other.counts.invalid;
+        for (key, counts) in other.by_character {
+            let entry = self.by_character.entry(key).or_default();
+            entry.valid += counts.valid;
+            entry.invalid += counts.invalid;
+        }
+        for (reason, count) in other.failures {
+            *self.failures.entry(reason).or_insert(0) += count;
+        }
+        for (length, count) in other.lengths {
+            *self.lengths.entry(length).or_insert(0) += count;
+        }
+        self.offenders.extend(other.offenders);
+        self.offenders.sort_by_key(|offender| offender.line);
+        self.offenders.truncate(self.max_offenders);
+        self
+    }
+}
+
+impl fmt::Display for AuditReport {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "audited {} lines: {} valid, {} invalid",
+            self.counts.valid + self.counts.invalid, self.counts.valid, self.counts.invalid)?;
+        writeln!(f, "by policy character:")?;
+        for (key, counts) in &self.by_character {
+            writeln!(f, "  {}: {} valid, {} invalid", key, counts.valid, counts.invalid)?;
+        }
+        writeln!(f, "by failure reason:")?;
+        for (reason, count) in &self.failures {
+            writeln!(f, "  {}: {}", reason, count)?;
+        }
+        writeln!(f, "password lengths:")?;
+        for (length, count) in &self.lengths {
+            writeln!(f, "  {:>3}: {}", length, count)?;
+        }
+        write!(f, "first {} offending lines:", self.offenders.len())?;
+        for offender in &self.offenders {
+            write!(f, "\n  line {}: {} ({})", offender.line, offender.text, offender.reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::stream::process_reader;
+
+    const SAMPLE : &str = "1-3 a: abcde
+1-3 b: cdefg
+2-9 c: ccccccccc
+
+1-9 a: abc
+count 1-2 a: banana
+length 1-3: abc
+2-3 a: bab";
+
+    fn audit(input : &str, threads : usize, max_offenders : usize) -> AuditReport {
+        process_reader(input.as_bytes(), threads, 8, || AuditReport::new(max_offenders)).unwrap()
+    }
+
+    #[test]
+    fn counts_and_failures() {
+        let report = audit(SAMPLE, 1, 10);
+        assert_eq!(report.counts, Counts { valid: 3, invalid: 4 });
+        assert_eq!(report.by_character.get("a"), Some(&Counts { valid: 2, invalid: 2 }));
+        assert_eq!(report.by_character.get("b"), Some(&Counts { valid: 0, invalid: 1 }));
+        assert_eq!(report.by_character.get("c"), Some(&Counts { valid: 0, invalid: 1 }));
+        assert_eq!(report.by_character.get(NO_CHARACTER), Some(&Counts { valid: 1, invalid: 0 }));
+        assert_eq!(report.failures, vec![
+            ("both positions match", 1),
+            ("count out of range", 1),
+            ("neither position matches", 1),
+            ("position out of range", 1),
+        ].into_iter().collect());
+        assert_eq!(report.lengths, vec![(3, 3), (5, 2), (6, 1), (9, 1)].into_iter().collect());
+    }
+
+    #[test]
+    fn first_offenders_in_line_order() {
+        for threads in 1..=3 {
+            let report = audit(SAMPLE, threads, 2);
+            assert_eq!(report.offenders, vec![
+                Offender { line: 2, text: "1-3 b: cdefg".to_string(), reason: "neither position 1 nor 3 contains 'b'".to_string() },
+                Offender { line: 3, text: "2-9 c: ccccccccc".to_string(), reason: "both positions 2 and 9 contain 'c'".to_string() },
+            ]);
+            assert_eq!(report, audit(SAMPLE, 1, 2));
+        }
+    }
+
+    #[test]
+    fn json_output() {
+        let report = audit("1-3 a: abcde\n1-3 \": \"b\"", 1, 5);
+        assert_eq!(report.to_json(), concat!(
+            "{\"lines\":2,\"valid\":1,\"invalid\":1,",
+            "\"by_character\":{\"\\\"\":{\"valid\":0,\"invalid\":1},\"a\":{\"valid\":1,\"invalid\":0}},",
+            "\"failures\":{\"both positions match\":1},",
+            "\"lengths\":{\"3\":1,\"5\":1},",
+            "\"offenders\":[{\"line\":2,\"text\":\"1-3 \\\": \\\"b\\\"\",\"reason\":\"both positions 1 and 3 contain '\\\"'\"}]}"));
+    }
+
+    #[test]
+    fn text_output() {
+        let report = audit("1-3 a: abcde\n1-3 a: aba", 1, 5);
+        assert_eq!(report.to_string(), "audited 2 lines: 1 valid, 1 invalid
+by policy character:
+  a: 1 valid, 1 invalid
+by failure reason:
+  both positions match: 1
+password lengths:
+    3: 1
+    5: 1
+first 1 offending lines:
+  line 2: 1-3 a: aba (both positions 1 and 3 contain 'a')");
+    }
+}
@@ -0,0 +1,239 @@
+use super::PasswordPolicy;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+//splitmix64, small and reproducible across platforms for a given seed
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state : u64,
+}
+
+impl Rng {
+    pub fn new(seed : u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    //uniform value in min..=max
+    pub fn range(&mut self, min : usize, max : usize) -> usize {
+        min + (self.next_u64() % (max - min + 1) as u64) as usize
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Outcome {
+    Valid,
+    BothPositionsMatch,
+    NeitherPositionMatches,
+    OutOfRange,
+}
+
+const OUTCOMES : [Outcome; 4] = [
+    Outcome::Valid,
+    Outcome::BothPositionsMatch,
+    Outcome::NeitherPositionMatches,
+    Outcome::OutOfRange,
+];
+
+#[derive(Debug)]
+pub struct OutcomeParseError;
+
+impl Error for OutcomeParseError {}
+impl fmt::Display for OutcomeParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse outcome, expected one of valid, both, neither or out-of-range")
+    }
+}
+
+impl FromStr for Outcome {
+    type Err = OutcomeParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s {
+            "valid" => Ok(Outcome::Valid),
+            "both" => Ok(Outcome::BothPositionsMatch),
+            "neither" => Ok(Outcome::NeitherPositionMatches),
+            "out-of-range" => Ok(Outcome::OutOfRange),
+            _ => Err(OutcomeParseError),
+        }
+    }
+}
+
+const ALPHABET : &str = "abcdefghijklmnopqrstuvwxyz";
+
+pub struct PasswordGenerator {
+    rng : Rng,
+    alphabet : Vec<char>,
+    max_position : usize,
+}
+
+impl PasswordGenerator {
+    pub fn new(seed : u64) -> PasswordGenerator {
+        PasswordGenerator {
+            rng: Rng::new(seed),
+            alphabet: ALPHABET.chars().collect(),
+            max_position: 20,
+        }
+    }
+
+    fn character(&mut self) -> char {
+        self.alphabet[self.rng.range(0, self.alphabet.len() - 1)]
+    }
+
+    fn other_than(&mut self, c : char) -> char {
+        loop {
+            let other = self.character();
+            if other != c {
+                return other;
+            }
+        }
+    }
+
+    pub fn policy(&mut self) -> PasswordPolicy {
+        let position1 = self.rng.range(1, self.max_position - 1);
+        PasswordPolicy {
+            position1,
+            position2: self.rng.range(position1 + 1, self.max_position),
+            character: self.character(),
+        }
+    }
+
+    //a password that gives the outcome under the policy, or None when no such password exists
+    pub fn password(&mut self, policy : &PasswordPolicy, outcome : Outcome) -> Option<String> {
+        let first = policy.position1.min(policy.position2);
+        let last = policy.position1.max(policy.position2);
+        let length = match outcome {
+            Outcome::OutOfRange if first == 0 => self.rng.range(0, last + 3),
+            Outcome::OutOfRange => self.rng.range(0, last - 1),
+            _ if first == 0 => return None,
+            Outcome::Valid if first == last => return None,
+            _ => self.rng.range(last, last + 5),
+        };
+        let mut password : Vec<char> = (0..length)
+            .map(|_| self.other_than(policy.character))
+            .collect();
+        match outcome {
+            Outcome::Valid => {
+                let position = if self.rng.range(0, 1) == 0 { first } else { last };
+                password[position - 1] = policy.character;
+            },
+            Outcome::BothPositionsMatch => {
+                password[first - 1] = policy.character;
+                password[last - 1] = policy.character;
+            },
+            Outcome::NeitherPositionMatches => {},
+            Outcome::OutOfRange => {
+                //anything goes outside the two positions, including the policy character
+                for c in password.iter_mut() {
+                    if self.rng.range(0, 3) == 0 {
+                        *c = policy.character;
+                    }
+                }
+            },
+        }
+        Some(password.into_iter().collect())
+    }
+
+    //a random `N-M c: password` line with the given outcome
+    pub fn line(&mut self, outcome : Outcome) -> String {
+        loop {
+            let policy = self.policy();
+            if let Some(password) = self.password(&policy, outcome) {
+                return format!("{}: {}", policy, password);
+            }
+        }
+    }
+
+    pub fn write_lines<W : Write>(&mut self, writer : &mut W, lines : usize, outcomes : &[Outcome]) -> io::Result<()> {
+        let outcomes = if outcomes.is_empty() { &OUTCOMES[..] } else { outcomes };
+        for _ in 0..lines {
+            let outcome = outcomes[self.rng.range(0, outcomes.len() - 1)];
+            writeln!(writer, "{}", self.line(outcome))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Password;
+    use super::super::rules::RuleFailure;
+    use super::super::stream::validate_reader;
+
+    fn expected_kind(outcome : Outcome) -> Option<&'static str> {
+        match outcome {
+            Outcome::Valid => None,
+            Outcome::BothPositionsMatch => Some("both positions match"),
+            Outcome::NeitherPositionMatches => Some("neither position matches"),
+            Outcome::OutOfRange => Some("position out of range"),
+        }
+    }
+
+    #[test]
+    fn same_seed_same_passwords() {
+        let mut first = PasswordGenerator::new(42);
+        let mut second = PasswordGenerator::new(42);
+        for outcome in OUTCOMES.iter() {
+            assert_eq!(first.line(*outcome), second.line(*outcome));
+        }
+        assert_ne!(PasswordGenerator::new(1).line(Outcome::Valid), PasswordGenerator::new(2).line(Outcome::Valid));
+    }
+
+    #[test]
+    fn generated_lines_round_trip() {
+        for seed in 0..200 {
+            let mut generator = PasswordGenerator::new(seed);
+            for outcome in OUTCOMES.iter() {
+                let line = generator.line(*outcome);
+                let password = Password::parse(line.as_str()).unwrap();
+                assert_eq!(password.check().err().as_ref().map(RuleFailure::kind), expected_kind(*outcome), "{}", line);
+                assert_eq!(password.is_valid(), *outcome == Outcome::Valid, "{}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn edge_case_policies() {
+        let mut generator = PasswordGenerator::new(7);
+        let same = PasswordPolicy { position1: 4, position2: 4, character: 'é' };
+        assert_eq!(generator.password(&same, Outcome::Valid), None);
+        let zero = PasswordPolicy { position1: 0, position2: 3, character: 'a' };
+        assert_eq!(generator.password(&zero, Outcome::NeitherPositionMatches), None);
+        for _ in 0..50 {
+            for (policy, outcome) in &[(same, Outcome::BothPositionsMatch), (same, Outcome::NeitherPositionMatches), (zero, Outcome::OutOfRange)] {
+                let password = generator.password(policy, *outcome).unwrap();
+                let line = format!("{}: {}", policy, password);
+                let failure = Password::parse(line.as_str()).unwrap().check().unwrap_err();
+                assert_eq!(Some(failure.kind()), expected_kind(*outcome), "{}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn synthetic_file_counts() {
+        let mut output : Vec<u8> = vec![];
+        PasswordGenerator::new(2020).write_lines(&mut output, 1000, &[Outcome::Valid, Outcome::OutOfRange]).unwrap();
+        let valid = String::from_utf8(output.clone()).unwrap().lines()
+            .filter(|line| Password::parse(line).unwrap().is_valid())
+            .count();
+        let summary = validate_reader(output.as_slice(), 2).unwrap();
+        assert_eq!(summary.lines, 1000);
+        assert_eq!(summary.valid, valid);
+        assert!(valid > 400 && valid < 600, "{}", valid);
+    }
+
+    #[test]
+    fn parse_outcomes() {
+        assert_eq!("both".parse::<Outcome>().unwrap(), Outcome::BothPositionsMatch);
+        assert!("sometimes".parse::<Outcome>().is_err());
+    }
+}
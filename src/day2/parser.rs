@@ -0,0 +1,359 @@
+//! Parser for password policy lines.
+//!
+//! A line is a policy followed by `:` and the password. Policies use the
+//! following grammar, where keywords are case sensitive and whitespace is
+//! allowed between any two tokens:
+//!
+//! ```text
+//! line       := policy ":" password
+//! policy     := and ( "or" and )*
+//! and        := unary ( "and" unary )*
+//! unary      := "not" unary | "(" policy ")" | term
+//! term       := range characters                  legacy form, same as `positions`
+//!             | "positions" range characters      exactly one position holds one of the characters
+//!             | "count" range characters          number of occurrences is within the range
+//!             | "length" range                    password length is within the range
+//!             | "requires" class                  at least one character of the class
+//!             | "forbids" string                  password must not contain the string
+//! range      := number "-" number
+//! characters := one or more characters other than whitespace, ':', '(' and ')'
+//! class      := "lowercase" | "uppercase" | "digit" | "symbol"
+//! string     := '"' ( any character, with \" and \\ escaped )* '"'
+//! ```
+//!
+//! So `1-3 a: abcde` keeps its meaning, while a line such as
+//! `count 1-3 ab and not forbids "password": hunter2` mixes policy kinds.
+use super::rules::{CharClass, Rule};
+use std::{error::Error, fmt};
+
+#[cfg(test)]
+use rstest::rstest;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PolicyParseError {
+    //1-based column in the line where parsing failed
+    pub column : usize,
+    pub message : String,
+}
+
+impl Error for PolicyParseError {}
+impl fmt::Display for PolicyParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse policy at column {}: {}", self.column, self.message)
+    }
+}
+
+//scans the line in place, `position` is a byte offset so no copy of the line is made
+struct Parser<'a> {
+    input : &'a str,
+    position : usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input : &'a str) -> Self {
+        Parser {
+            input,
+            position: 0,
+        }
+    }
+
+    fn error<T>(&self, message : String) -> Result<T, PolicyParseError> {
+        Err(PolicyParseError {
+            column: self.input[..self.position].chars().count() + 1,
+            message,
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn advance(&mut self) {
+        if let Some(c) = self.peek() {
+            self.position += c.len_utf8();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+    }
+
+    fn take_while<F>(&mut self, f : F) -> &'a str where F : Fn(char) -> bool {
+        let start = self.position;
+        while self.peek().is_some_and(&f) {
+            self.advance();
+        }
+        &self.input[start..self.position]
+    }
+
+    fn peek_word(&self) -> &'a str {
+        let rest = &self.input[self.position..];
+        let end = rest.find(|c : char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        &rest[..end]
+    }
+
+    fn eat_keyword(&mut self, keyword : &str) -> bool {
+        self.skip_whitespace();
+        if self.peek_word() == keyword {
+            self.position += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected : char) -> Result<(), PolicyParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.advance();
+                Ok(())
+            },
+            Some(c) => self.error(format!("expected '{}' but found '{}'", expected, c)),
+            None => self.error(format!("expected '{}' but found end of line", expected)),
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, PolicyParseError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        if digits.is_empty() {
+            return self.error("expected a number".to_string());
+        }
+        digits.parse::<usize>().or_else(|_| {
+            self.position = start;
+            self.error(format!("number {} is too large", digits))
+        })
+    }
+
+    fn range(&mut self) -> Result<(usize, usize), PolicyParseError> {
+        let start = self.position;
+        let min = self.number()?;
+        self.expect('-')?;
+        let max = self.number()?;
+        if min > max {
+            self.position = start;
+            self.skip_whitespace();
+            return self.error(format!("range {}-{} is empty", min, max));
+        }
+        Ok((min, max))
+    }
+
+    fn characters(&mut self) -> Result<String, PolicyParseError> {
+        self.skip_whitespace();
+        let characters = self.take_while(|c| !c.is_whitespace() && c != ':' && c != '(' && c != ')');
+        if characters.is_empty() {
+            self.error("expected one or more characters".to_string())
+        } else {
+            Ok(characters.to_string())
+        }
+    }
+
+    fn string(&mut self) -> Result<String, PolicyParseError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated string".to_string()),
+                Some('"') => {
+                    self.advance();
+                    return Ok(s);
+                },
+                Some('\\') => {
+                    self.advance();
+                    match self.peek() {
+                        Some(c) if c == '"' || c == '\\' => s.push(c),
+                        _ => return self.error("invalid escape in string".to_string()),
+                    }
+                    self.advance();
+                },
+                Some(c) => {
+                    s.push(c);
+                    self.advance();
+                },
+            }
+        }
+    }
+
+    fn class(&mut self) -> Result<CharClass, PolicyParseError> {
+        self.skip_whitespace();
+        let word = self.peek_word();
+        let class = match word {
+            "lowercase" => CharClass::Lowercase,
+            "uppercase" => CharClass::Uppercase,
+            "digit" => CharClass::Digit,
+            "symbol" => CharClass::Symbol,
+            _ => return self.error(format!("unknown character class {:?}", word)),
+        };
+        self.position += word.len();
+        Ok(class)
+    }
+
+    fn term(&mut self) -> Result<Rule, PolicyParseError> {
+        self.skip_whitespace();
+        if self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            let (position1, position2) = self.range()?;
+            let characters = self.characters()?;
+            return Ok(Rule::Positional { position1, position2, characters });
+        }
+        let word = self.peek_word();
+        let rule = match word {
+            "positions" => {
+                self.position += word.len();
+                let (position1, position2) = self.range()?;
+                let characters = self.characters()?;
+                Rule::Positional { position1, position2, characters }
+            },
+            "count" => {
+                self.position += word.len();
+                let (min, max) = self.range()?;
+                let characters = self.characters()?;
+                Rule::Count { min, max, characters }
+            },
+            "length" => {
+                self.position += word.len();
+                let (min, max) = self.range()?;
+                Rule::Length { min, max }
+            },
+            "requires" => {
+                self.position += word.len();
+                Rule::RequiresClass(self.class()?)
+            },
+            "forbids" => {
+                self.position += word.len();
+                Rule::Forbids(self.string()?)
+            },
+            "" => return match self.peek() {
+                Some(c) => self.error(format!("expected a policy but found '{}'", c)),
+                None => self.error("expected a policy but found end of line".to_string()),
+            },
+            _ => return self.error(format!("unknown policy kind {:?}", word)),
+        };
+        Ok(rule)
+    }
+
+    fn unary(&mut self) -> Result<Rule, PolicyParseError> {
+        if self.eat_keyword("not") {
+            return Ok(Rule::Not(Box::new(self.unary()?)));
+        }
+        self.skip_whitespace();
+        if self.peek() == Some('(') {
+            self.advance();
+            let rule = self.policy()?;
+            self.expect(')')?;
+            return Ok(rule);
+        }
+        self.term()
+    }
+
+    fn and(&mut self) -> Result<Rule, PolicyParseError> {
+        let mut rules = vec![self.unary()?];
+        while self.eat_keyword("and") {
+            rules.push(self.unary()?);
+        }
+        Ok(if rules.len() == 1 { rules.remove(0) } else { Rule::All(rules) })
+    }
+
+    fn policy(&mut self) -> Result<Rule, PolicyParseError> {
+        let mut rules = vec![self.and()?];
+        while self.eat_keyword("or") {
+            rules.push(self.and()?);
+        }
+        Ok(if rules.len() == 1 { rules.remove(0) } else { Rule::Any(rules) })
+    }
+}
+
+//parses `policy: password`, returning the rule and the trimmed password
+pub fn parse_line(s : &str) -> Result<(Rule, &str), PolicyParseError> {
+    let mut parser = Parser::new(s);
+    let rule = parser.policy()?;
+    parser.expect(':')?;
+    let password = &parser.input[parser.position..];
+    Ok((rule, password.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a whole line of policy, with nothing after it
+    fn parse_policy(s : &str) -> Result<Rule, PolicyParseError> {
+        let mut parser = Parser::new(s);
+        let rule = parser.policy()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(rule),
+            Some(c) => parser.error(format!("unexpected '{}' after policy", c)),
+        }
+    }
+
+    fn positional(position1 : usize, position2 : usize, characters : &str) -> Rule {
+        Rule::Positional { position1, position2, characters: characters.to_string() }
+    }
+
+    #[test]
+    fn legacy_form() {
+        assert_eq!(parse_line("1-3 a: abcde"), Ok((positional(1, 3, "a"), "abcde")));
+        assert_eq!(parse_line("13-14 f:ffffffffnfffvv"), Ok((positional(13, 14, "f"), "ffffffffnfffvv")));
+    }
+
+    #[rstest(input, rule,
+        case("positions 1-3 ab", positional(1, 3, "ab")),
+        case("count 2-9 c", Rule::Count { min: 2, max: 9, characters: "c".to_string() }),
+        case("length 8-64", Rule::Length { min: 8, max: 64 }),
+        case("requires digit", Rule::RequiresClass(CharClass::Digit)),
+        case("forbids \"pass\\\"word\"", Rule::Forbids("pass\"word".to_string())),
+        case("not requires symbol", Rule::Not(Box::new(Rule::RequiresClass(CharClass::Symbol)))),
+        case("length 8-64 and requires digit or forbids \"x\"", Rule::Any(vec![
+            Rule::All(vec![Rule::Length { min: 8, max: 64 }, Rule::RequiresClass(CharClass::Digit)]),
+            Rule::Forbids("x".to_string()),
+        ])),
+        case("length 8-64 and (requires digit or requires symbol)", Rule::All(vec![
+            Rule::Length { min: 8, max: 64 },
+            Rule::Any(vec![Rule::RequiresClass(CharClass::Digit), Rule::RequiresClass(CharClass::Symbol)]),
+        ])),
+        ::trace
+    )]
+    fn parse_policies(input : &str, rule : Rule) {
+        assert_eq!(parse_policy(input), Ok(rule));
+    }
+
+    #[rstest(input, column, message,
+        case("", 1, "expected a policy but found end of line"),
+        case("1-3", 4, "expected one or more characters"),
+        case("1 a", 3, "expected '-' but found 'a'"),
+        case("3-1 a", 1, "range 3-1 is empty"),
+        case("colour 1-3 a", 1, "unknown policy kind \"colour\""),
+        case("requires vowel", 10, "unknown character class \"vowel\""),
+        case("forbids \"abc", 13, "unterminated string"),
+        case("(length 1-2", 12, "expected ')' but found end of line"),
+        case("length 1-2 a", 12, "unexpected 'a' after policy"),
+        ::trace
+    )]
+    fn positioned_errors(input : &str, column : usize, message : &str) {
+        assert_eq!(parse_policy(input), Err(PolicyParseError { column, message: message.to_string() }));
+    }
+
+    #[test]
+    fn missing_password_separator() {
+        assert_eq!(parse_line("1-3 a abcde"), Err(PolicyParseError {
+            column: 7,
+            message: "expected ':' but found 'a'".to_string(),
+        }));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let input = "not (count 1-3 ab or length 2-4) and forbids \"a\\\\b\" and positions 1-2 é";
+        let rule = parse_policy(input).unwrap();
+        assert_eq!(parse_policy(rule.to_string().as_str()), Ok(rule));
+    }
+
+    #[test]
+    fn password_after_multibyte_policy() {
+        assert_eq!(parse_line("1-2 é: éa"), Ok((positional(1, 2, "é"), "éa")));
+    }
+}
@@ -0,0 +1,311 @@
+use std::fmt;
+
+#[cfg(test)]
+use rstest::rstest;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    fn matches(&self, c : char) -> bool {
+        match self {
+            CharClass::Lowercase => c.is_lowercase(),
+            CharClass::Uppercase => c.is_uppercase(),
+            CharClass::Digit => c.is_ascii_digit(),
+            CharClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CharClass::Lowercase => "lowercase",
+            CharClass::Uppercase => "uppercase",
+            CharClass::Digit => "digit",
+            CharClass::Symbol => "symbol",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Rule {
+    //exactly one of the two (1-based) positions holds one of the characters
+    Positional { position1 : usize, position2 : usize, characters : String },
+    Count { min : usize, max : usize, characters : String },
+    Length { min : usize, max : usize },
+    RequiresClass(CharClass),
+    Forbids(String),
+    All(Vec<Rule>),
+    Any(Vec<Rule>),
+    Not(Box<Rule>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuleFailure {
+    BothPositionsMatch { position1 : usize, position2 : usize, characters : String },
+    NeitherPositionMatches { position1 : usize, position2 : usize, characters : String },
+    PositionOutOfRange { position : usize, length : usize },
+    CountOutOfRange { characters : String, count : usize, min : usize, max : usize },
+    LengthOutOfRange { length : usize, min : usize, max : usize },
+    MissingClass(CharClass),
+    ContainsForbidden(String),
+    NoAlternativeMatched(Vec<RuleFailure>),
+    NegatedRuleMatched(Rule),
+}
+
+impl Rule {
+    pub fn evaluate(&self, password : &str) -> Result<(), RuleFailure> {
+        match self {
+            Rule::Positional { position1, position2, characters } => {
+                let length = password.chars().count();
+                let at = |position : usize| -> Result<bool, RuleFailure> {
+                    if position == 0 || position > length {
+                        Err(RuleFailure::PositionOutOfRange { position, length })
+                    } else {
+                        Ok(password.chars().nth(position - 1).is_some_and(|c| characters.contains(c)))
+                    }
+                };
+                match (at(*position1)?, at(*position2)?) {
+                    (true, true) => Err(RuleFailure::BothPositionsMatch {
+                        position1: *position1,
+                        position2: *position2,
+                        characters: characters.clone(),
+                    }),
+                    (false, false) => Err(RuleFailure::NeitherPositionMatches {
+                        position1: *position1,
+                        position2: *position2,
+                        characters: characters.clone(),
+                    }),
+                    _ => Ok(()),
+                }
+            },
+            Rule::Count { min, max, characters } => {
+                let count = password.chars().filter(|c| characters.contains(*c)).count();
+                if (*min..=*max).contains(&count) {
+                    Ok(())
+                } else {
+                    Err(RuleFailure::CountOutOfRange { characters: characters.clone(), count, min: *min, max: *max })
+                }
+            },
+            Rule::Length { min, max } => {
+                let length = password.chars().count();
+                if (*min..=*max).contains(&length) {
+                    Ok(())
+                } else {
+                    Err(RuleFailure::LengthOutOfRange { length, min: *min, max: *max })
+                }
+            },
+            Rule::RequiresClass(class) => {
+                if password.chars().any(|c| class.matches(c)) {
+                    Ok(())
+                } else {
+                    Err(RuleFailure::MissingClass(*class))
+                }
+            },
+            Rule::Forbids(s) => {
+                if password.contains(s.as_str()) {
+                    Err(RuleFailure::ContainsForbidden(s.clone()))
+                } else {
+                    Ok(())
+                }
+            },
+            Rule::All(rules) => rules.iter()
+                .try_for_each(|rule| rule.evaluate(password)),
+            Rule::Any(rules) => {
+                let mut failures = vec![];
+                for rule in rules {
+                    match rule.evaluate(password) {
+                        Ok(()) => return Ok(()),
+                        Err(failure) => failures.push(failure),
+                    }
+                }
+                Err(RuleFailure::NoAlternativeMatched(failures))
+            },
+            Rule::Not(rule) => match rule.evaluate(password) {
+                Ok(()) => Err(RuleFailure::NegatedRuleMatched(*rule.clone())),
+                Err(_) => Ok(()),
+            },
+        }
+    }
+}
+
+impl RuleFailure {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuleFailure::BothPositionsMatch { .. } => "both positions match",
+            RuleFailure::NeitherPositionMatches { .. } => "neither position matches",
+            RuleFailure::PositionOutOfRange { .. } => "position out of range",
+            RuleFailure::CountOutOfRange { .. } => "count out of range",
+            RuleFailure::LengthOutOfRange { .. } => "length out of range",
+            RuleFailure::MissingClass(_) => "missing character class",
+            RuleFailure::ContainsForbidden(_) => "contains forbidden text",
+            RuleFailure::NoAlternativeMatched(_) => "no alternative matched",
+            RuleFailure::NegatedRuleMatched(_) => "negated rule matched",
+        }
+    }
+}
+
+fn write_characters(f:&mut fmt::Formatter, characters : &str) -> fmt::Result {
+    if characters.chars().count() == 1 {
+        write!(f, "'{}'", characters)
+    } else {
+        write!(f, "one of \"{}\"", characters)
+    }
+}
+
+//writes the rule back out in the policy grammar understood by the parser
+impl fmt::Display for Rule {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        let join = |f:&mut fmt::Formatter, rules : &[Rule], op : &str| -> fmt::Result {
+            write!(f, "(")?;
+            for (i, rule) in rules.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", op)?;
+                }
+                write!(f, "{}", rule)?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            Rule::Positional { position1, position2, characters } =>
+                write!(f, "positions {}-{} {}", position1, position2, characters),
+            Rule::Count { min, max, characters } =>
+                write!(f, "count {}-{} {}", min, max, characters),
+            Rule::Length { min, max } => write!(f, "length {}-{}", min, max),
+            Rule::RequiresClass(class) => write!(f, "requires {}", class.name()),
+            Rule::Forbids(s) => write!(f, "forbids \"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+            Rule::All(rules) => join(f, rules, "and"),
+            Rule::Any(rules) => join(f, rules, "or"),
+            Rule::Not(rule) => write!(f, "not {}", rule),
+        }
+    }
+}
+
+impl fmt::Display for RuleFailure {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleFailure::BothPositionsMatch { position1, position2, characters } => {
+                write!(f, "both positions {} and {} contain ", position1, position2)?;
+                write_characters(f, characters)
+            },
+            RuleFailure::NeitherPositionMatches { position1, position2, characters } => {
+                write!(f, "neither position {} nor {} contains ", position1, position2)?;
+                write_characters(f, characters)
+            },
+            RuleFailure::PositionOutOfRange { position, length } =>
+                write!(f, "position {} is outside of a password of length {}", position, length),
+            RuleFailure::CountOutOfRange { characters, count, min, max } => {
+                write_characters(f, characters)?;
+                write!(f, " occurs {} times, expected {} to {}", count, min, max)
+            },
+            RuleFailure::LengthOutOfRange { length, min, max } =>
+                write!(f, "length is {}, expected {} to {}", length, min, max),
+            RuleFailure::MissingClass(class) =>
+                write!(f, "missing a {} character", class.name()),
+            RuleFailure::ContainsForbidden(s) =>
+                write!(f, "contains forbidden {:?}", s),
+            RuleFailure::NoAlternativeMatched(failures) => {
+                write!(f, "no alternative matched (")?;
+                for (i, failure) in failures.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", failure)?;
+                }
+                write!(f, ")")
+            },
+            RuleFailure::NegatedRuleMatched(rule) =>
+                write!(f, "must not match `{}`", rule),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positional(position1 : usize, position2 : usize, characters : &str) -> Rule {
+        Rule::Positional { position1, position2, characters: characters.to_string() }
+    }
+
+    #[rstest(password, result,
+        case("abcde", Ok(())),
+        case("cbade", Ok(())),
+        case("abade", Err(RuleFailure::BothPositionsMatch { position1: 1, position2: 3, characters: "a".to_string() })),
+        case("bbbde", Err(RuleFailure::NeitherPositionMatches { position1: 1, position2: 3, characters: "a".to_string() })),
+        case("ab", Err(RuleFailure::PositionOutOfRange { position: 3, length: 2 })),
+        case("", Err(RuleFailure::PositionOutOfRange { position: 1, length: 0 })),
+        ::trace
+    )]
+    fn positional_rule(password : &str, result : Result<(), RuleFailure>) {
+        assert_eq!(positional(1, 3, "a").evaluate(password), result);
+    }
+
+    #[test]
+    fn position_zero_is_out_of_range() {
+        assert_eq!(positional(0, 1, "a").evaluate("a"), Err(RuleFailure::PositionOutOfRange { position: 0, length: 1 }));
+    }
+
+    #[rstest(rule, password, result,
+        case(Rule::Count { min: 1, max: 3, characters: "a".to_string() }, "banana", Ok(())),
+        case(Rule::Count { min: 1, max: 2, characters: "a".to_string() }, "banana",
+            Err(RuleFailure::CountOutOfRange { characters: "a".to_string(), count: 3, min: 1, max: 2 })),
+        case(Rule::Length { min: 8, max: 64 }, "hunter2",
+            Err(RuleFailure::LengthOutOfRange { length: 7, min: 8, max: 64 })),
+        case(Rule::RequiresClass(CharClass::Digit), "hunter2", Ok(())),
+        case(Rule::RequiresClass(CharClass::Uppercase), "hunter2", Err(RuleFailure::MissingClass(CharClass::Uppercase))),
+        case(Rule::RequiresClass(CharClass::Symbol), "hunter2!", Ok(())),
+        case(Rule::Forbids("password".to_string()), "mypassword1",
+            Err(RuleFailure::ContainsForbidden("password".to_string()))),
+        ::trace
+    )]
+    fn single_rule(rule : Rule, password : &str, result : Result<(), RuleFailure>) {
+        assert_eq!(rule.evaluate(password), result);
+    }
+
+    #[test]
+    fn all_reports_first_failure() {
+        let rule = Rule::All(vec![
+            Rule::Length { min: 4, max: 10 },
+            Rule::RequiresClass(CharClass::Digit),
+            Rule::Forbids("abc".to_string()),
+        ]);
+        assert_eq!(rule.evaluate("abcd1"), Err(RuleFailure::ContainsForbidden("abc".to_string())));
+        assert_eq!(rule.evaluate("xyz"), Err(RuleFailure::LengthOutOfRange { length: 3, min: 4, max: 10 }));
+        assert_eq!(rule.evaluate("wxyz1"), Ok(()));
+    }
+
+    #[test]
+    fn any_reports_every_failure() {
+        let rule = Rule::Any(vec![
+            Rule::RequiresClass(CharClass::Digit),
+            Rule::RequiresClass(CharClass::Symbol),
+        ]);
+        assert_eq!(rule.evaluate("abc!"), Ok(()));
+        let failure = rule.evaluate("abc").unwrap_err();
+        assert_eq!(failure, RuleFailure::NoAlternativeMatched(vec![
+            RuleFailure::MissingClass(CharClass::Digit),
+            RuleFailure::MissingClass(CharClass::Symbol),
+        ]));
+        assert_eq!(failure.to_string(), "no alternative matched (missing a digit character; missing a symbol character)");
+    }
+
+    #[test]
+    fn multiple_characters() {
+        let rule = Rule::Count { min: 2, max: 3, characters: "ab".to_string() };
+        assert_eq!(rule.evaluate("abc"), Ok(()));
+        assert_eq!(rule.evaluate("aaab").unwrap_err().to_string(), "one of \"ab\" occurs 4 times, expected 2 to 3");
+        assert_eq!(positional(1, 2, "xy").evaluate("yz"), Ok(()));
+    }
+
+    #[test]
+    fn not_fails_when_inner_rule_matches() {
+        let rule = Rule::Not(Box::new(Rule::Forbids("abc".to_string())));
+        assert_eq!(rule.evaluate("xabcx"), Ok(()));
+        assert_eq!(rule.evaluate("xyz").unwrap_err().to_string(), "must not match `forbids \"abc\"`");
+    }
+}
@@ -0,0 +1,264 @@
+use super::Password;
+use super::parser::PolicyParseError;
+use std::io::{self, BufRead};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::{error::Error, fmt};
+
+//lines are handed out to workers in chunks of roughly this many bytes
+pub const CHUNK_SIZE : usize = 1 << 20;
+
+//collects results for parsed lines, each worker fills its own sink and they are merged at the end
+pub trait LineSink {
+    fn record(&mut self, line_number : usize, line : &str, password : &Password);
+    fn merge(self, other : Self) -> Self;
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct ValidationSummary {
+    pub lines : usize,
+    pub valid : usize,
+}
+
+impl LineSink for ValidationSummary {
+    fn record(&mut self, _line_number : usize, _line : &str, password : &Password) {
+        self.lines += 1;
+        if password.is_valid() {
+            self.valid += 1;
+        }
+    }
+
+    fn merge(self, other : ValidationSummary) -> ValidationSummary {
+        ValidationSummary {
+            lines: self.lines + other.lines,
+            valid: self.valid + other.valid,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PasswordFileError {
+    Io(io::Error),
+    InvalidUtf8 { line : usize },
+    Policy { line : usize, error : PolicyParseError },
+}
+
+impl PasswordFileError {
+    fn line(&self) -> usize {
+        match self {
+            PasswordFileError::Io(_) => 0,
+            PasswordFileError::InvalidUtf8 { line } => *line,
+            PasswordFileError::Policy { line, .. } => *line,
+        }
+    }
+}
+
+impl Error for PasswordFileError {}
+impl fmt::Display for PasswordFileError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PasswordFileError::Io(error) => write!(f, "Unable to read password file: {}", error),
+            PasswordFileError::InvalidUtf8 { line } => write!(f, "Unable to read password file: line {} is not valid UTF-8", line),
+            PasswordFileError::Policy { line, error } => write!(f, "line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::convert::From<io::Error> for PasswordFileError {
+    fn from(error : io::Error) -> PasswordFileError {
+        PasswordFileError::Io(error)
+    }
+}
+
+//parses every line of a chunk ending in a newline, first_line is the 1-based number of its first line
+fn process_chunk<S : LineSink>(chunk : &[u8], first_line : usize, sink : &mut S) -> Result<(), PasswordFileError> {
+    for (i, line) in chunk.split(|b| *b == b'\n').enumerate() {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            continue;
+        }
+        let line_number = first_line + i;
+        let line = std::str::from_utf8(line)
+            .map_err(|_| PasswordFileError::InvalidUtf8 { line: line_number })?;
+        let password = Password::parse(line)
+            .map_err(|error| PasswordFileError::Policy { line: line_number, error })?;
+        sink.record(line_number, line, &password);
+    }
+    Ok(())
+}
+
+//reads whole lines into the chunk until it holds at least chunk_size bytes, returning the number of lines read
+fn fill_chunk<R : BufRead>(reader : &mut R, chunk : &mut Vec<u8>, chunk_size : usize) -> io::Result<usize> {
+    chunk.clear();
+    let mut lines = 0;
+    while chunk.len() < chunk_size {
+        if reader.read_until(b'\n', chunk)? == 0 {
+            break;
+        }
+        lines += 1;
+    }
+    if chunk.last().is_some_and(|b| *b != b'\n') {
+        chunk.push(b'\n');
+    }
+    Ok(lines)
+}
+
+pub fn validate_reader<R : BufRead>(reader : R, threads : usize) -> Result<ValidationSummary, PasswordFileError> {
+    process_reader(reader, threads, CHUNK_SIZE, ValidationSummary::default)
+}
+
+pub fn process_reader<R, S, F>(mut reader : R, threads : usize, chunk_size : usize, new_sink : F) -> Result<S, PasswordFileError>
+    where R : BufRead, S : LineSink + Send, F : Fn() -> S + Sync {
+    if threads <= 1 {
+        let mut sink = new_sink();
+        let mut chunk = Vec::with_capacity(chunk_size.min(CHUNK_SIZE));
+        let mut line = 1;
+        loop {
+            let lines = fill_chunk(&mut reader, &mut chunk, chunk_size)?;
+            if lines == 0 {
+                return Ok(sink);
+            }
+            process_chunk(&chunk, line, &mut sink)?;
+            line += lines;
+        }
+    }
+
+    let (work_sender, work_receiver) = mpsc::sync_channel::<(usize, Vec<u8>)>(threads);
+    //a worker that fails drops the receiver, so the reader's next send fails instead of blocking on a full channel
+    let work_receiver = Mutex::new(Some(work_receiver));
+    //workers hand their buffers back so the reader can reuse them
+    let (free_sender, free_receiver) = mpsc::channel::<Vec<u8>>();
+
+    thread::scope(|scope| {
+        let workers : Vec<_> = (0..threads)
+            .map(|_| {
+                let work_receiver = &work_receiver;
+                let free_sender = free_sender.clone();
+                let new_sink = &new_sink;
+                scope.spawn(move || -> Result<S, PasswordFileError> {
+                    let mut sink = new_sink();
+                    loop {
+                        let next = work_receiver.lock().unwrap().as_ref().map(mpsc::Receiver::recv);
+                        let (first_line, chunk) = match next {
+                            Some(Ok(work)) => work,
+                            Some(Err(_)) | None => return Ok(sink),
+                        };
+                        let result = process_chunk(&chunk, first_line, &mut sink);
+                        let _ = free_sender.send(chunk);
+                        if result.is_err() {
+                            work_receiver.lock().unwrap().take();
+                        }
+                        result?;
+                    }
+                })
+            })
+            .collect();
+
+        let mut line = 1;
+        let read_result = loop {
+            let mut chunk = free_receiver.try_recv()
+                .unwrap_or_else(|_| Vec::with_capacity(chunk_size.min(CHUNK_SIZE)));
+            match fill_chunk(&mut reader, &mut chunk, chunk_size) {
+                Ok(0) => break Ok(()),
+                Ok(lines) => {
+                    //a worker has stopped on an error, which is reported below
+                    if work_sender.send((line, chunk)).is_err() {
+                        break Ok(());
+                    }
+                    line += lines;
+                },
+                Err(error) => break Err(error),
+            }
+        };
+        drop(work_sender);
+
+        let mut sink = new_sink();
+        let mut first_error : Option<PasswordFileError> = None;
+        for worker in workers {
+            match worker.join().expect("password validation worker panicked") {
+                Ok(worker_sink) => sink = sink.merge(worker_sink),
+                Err(error) => {
+                    if first_error.as_ref().is_none_or(|first| error.line() < first.line()) {
+                        first_error = Some(error);
+                    }
+                },
+            }
+        }
+        read_result?;
+        match first_error {
+            Some(error) => Err(error),
+            None => Ok(sink),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(lines : usize) -> String {
+        let mut s = String::new();
+        for i in 0..lines {
+            match i % 4 {
+                0 => s.push_str("1-3 a: abcde\n"),
+                1 => s.push_str("1-3 b: cdefg\r\n"),
+                2 => s.push_str("2-9 c: ccccccccc\n\n"),
+                _ => s.push_str("count 1-3 a and length 2-10: banana\n"),
+            }
+        }
+        s
+    }
+
+    #[test]
+    fn single_threaded() {
+        let summary = validate_reader(sample(8).as_bytes(), 1).unwrap();
+        assert_eq!(summary, ValidationSummary { lines: 8, valid: 4 });
+    }
+
+    #[test]
+    fn chunked_and_parallel_agree() {
+        let input = sample(1001);
+        let expected = process_reader(input.as_bytes(), 1, usize::MAX, ValidationSummary::default).unwrap();
+        assert_eq!(expected, ValidationSummary { lines: 1001, valid: 501 });
+        for threads in 1..=4 {
+            for chunk_size in &[1, 7, 64, 4096] {
+                assert_eq!(process_reader(input.as_bytes(), threads, *chunk_size, ValidationSummary::default).unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn missing_trailing_newline() {
+        let summary = process_reader("1-3 a: abcde\n1-3 a: bbade".as_bytes(), 2, 1, ValidationSummary::default).unwrap();
+        assert_eq!(summary, ValidationSummary { lines: 2, valid: 2 });
+    }
+
+    #[test]
+    fn reports_earliest_line_with_error() {
+        let mut input = sample(40);
+        input.push_str("1-3: abc\n");
+        input.push_str(sample(40).as_str());
+        input.push_str("1-x a: abc\n");
+        for threads in 1..=3 {
+            let error = process_reader(input.as_bytes(), threads, 16, ValidationSummary::default).unwrap_err();
+            assert_eq!(error.to_string(), "line 51: Unable to parse policy at column 4: expected one or more characters");
+        }
+    }
+
+    #[test]
+    fn stops_reading_after_an_error() {
+        let mut input = "1-x a: abc\n".repeat(10);
+        input.push_str(sample(2000).as_str());
+        for threads in 2..=4 {
+            let error = process_reader(input.as_bytes(), threads, 1, ValidationSummary::default).unwrap_err();
+            assert_eq!(error.to_string(), "line 1: Unable to parse policy at column 3: expected a number");
+        }
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let input : &[u8] = b"1-3 a: abcde\n1-3 a: \xff\xfe\n";
+        let error = validate_reader(input, 1).unwrap_err();
+        assert_eq!(error.to_string(), "Unable to read password file: line 2 is not valid UTF-8");
+    }
+}
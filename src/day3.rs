@@ -1,118 +1,330 @@
-use std::ops::Add;
+mod render;
+mod route;
+mod slopes;
+mod terrain;
+
+use std::collections::BTreeSet;
+use std::{error::Error, fmt};
+use std::fs;
+use std::io::{self, Write};
+use std::str::FromStr;
+use crate::geometry::Point;
+use crate::bitgrid::BitGrid;
+use crate::grid::{Grid, Topology};
 use crate::inputs::read_input;
+use crate::options::Options;
+use render::RenderFormat;
+use route::{Algorithm, MoveSet, RouteQuery};
+use slopes::{parse_slope, parse_slopes, Objective, SlopeTable};
+use terrain::Legend;
+
+#[cfg(test)]
+use rstest::rstest;
 
 #[derive(Debug, PartialEq, Copy,Clone)]
 enum ForestItem {
     Open,
     Tree,
+    //any other terrain from the legend
+    Terrain(u8),
     Unknown,
 }
-#[derive(Debug, PartialEq, Clone)]
+
+const PUZZLE_SLOPES : [Point; 5] = [
+    Point::new(1, 1),
+    Point::new(3, 1),
+    Point::new(5, 1),
+    Point::new(7, 1),
+    Point::new(1, 2),
+];
+
+//one bit per square for the trees, the map repeats to the right and to the left
+#[derive(PartialEq, Clone)]
 struct Forest {
-    rows: Vec<Vec<ForestItem>>,
-    width: usize,
+    trees : BitGrid,
+    //squares that are neither trees nor open, rare enough to be kept aside
+    unknown : BTreeSet<Point>,
+    //only there when the map uses terrains besides open ground and trees, 0 for those two
+    terrain : Option<Grid<u8>>,
+    legend : Legend,
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-struct Position {
-    x : usize,
-    y : usize
+#[derive(Debug, PartialEq, Clone)]
+enum ForestAnomaly {
+    Empty,
+    UnknownTile { line : usize, column : usize, tile : char },
+    RaggedRow { line : usize, expected : usize, found : usize },
 }
 
-impl Add for Position {
-    type Output = Self;
+impl fmt::Display for ForestAnomaly {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ForestAnomaly::Empty => write!(f, "no rows"),
+            ForestAnomaly::UnknownTile { line, column, tile } =>
+                write!(f, "unknown tile {:?} at line {}, column {}", tile, line, column),
+            ForestAnomaly::RaggedRow { line, expected, found } =>
+                write!(f, "line {} has {} squares, expected {}", line, found, expected),
+        }
+    }
+}
 
-    fn add(self, pos : Position)-> Position {
-        Position {
-            x: self.x + pos.x,
-            y: self.y + pos.y,
+#[derive(Debug, PartialEq, Clone)]
+struct ForestParseError(ForestAnomaly);
+
+impl Error for ForestParseError {}
+impl fmt::Display for ForestParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse forest: {}", self.0)
+    }
+}
+
+//strict, the first anomaly is an error
+impl FromStr for Forest {
+    type Err = ForestParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        Forest::parse_strict(s, Legend::default())
+    }
+}
+
+impl fmt::Display for Forest {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height() {
+            if y > 0 {
+                writeln!(f)?;
+            }
+            for item in self.row(y) {
+                write!(f, "{}", self.legend.symbol(item))?;
+            }
         }
+        Ok(())
+    }
+}
+
+//the map itself reads better than a list of enum names in failed assertions
+impl fmt::Debug for Forest {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Forest {}x{}\n{}", self.width(), self.height(), self)
     }
 }
 
 impl Forest {
+    #[cfg(test)]
+    fn parse_lenient(s : &str) -> Result<(Forest, Vec<ForestAnomaly>), ForestParseError> {
+        Forest::parse_with_legend(s, Legend::default())
+    }
 
-    fn multiply_all(&self) -> usize {
-        let slopes = vec! (
-            Position{x: 1, y: 1},
-            Position{x: 3, y: 1},
-            Position{x: 5, y: 1},
-            Position{x: 7, y: 1},
-            Position{x: 1, y: 2},
-        );
-        let trees_hit : Vec<usize> = slopes.iter()
-            .map(|x| self.how_many_trees_do_you_encounter(*x))
-            .collect();
-
-        let mut total = 1;
-        for tree_hit in trees_hit {
-            total = total * tree_hit;
-        }
-        total
-    }
-    fn parse_row(s : &str) -> Vec<ForestItem> {
-        let mut row : Vec<ForestItem> = vec!();
-        for c in s.chars() {
-            let item = if c == '#' {
-                ForestItem::Tree
-            } else if c == '.' {
-                ForestItem::Open
-            } else {
-                ForestItem::Unknown
-            };
-            row.push(item);
+    //strict like `from_str`, with the terrains of the legend
+    fn parse_strict(s : &str, legend : Legend) -> Result<Forest, ForestParseError> {
+        let (forest, anomalies) = Forest::parse_with_legend(s, legend)?;
+        match anomalies.into_iter().next() {
+            Some(anomaly) => Err(ForestParseError(anomaly)),
+            None => Ok(forest),
         }
-        row
     }
 
-    fn from_str(s:&str) -> Forest {
-        let rows : Vec<Vec<ForestItem>> = s.to_string().split("\n")
-            .filter(|x| !x.is_empty())
-            .map(Forest::parse_row)
-            .collect();
+    //keeps going past anomalies and lists them all, unknown tiles count as open and
+    //rows are padded with unknown tiles or cut down to the width of the first row
+    fn parse_with_legend(s : &str, legend : Legend) -> Result<(Forest, Vec<ForestAnomaly>), ForestParseError> {
+        let mut trees : Option<BitGrid> = None;
+        let mut terrain : Option<Vec<u8>> = None;
+        let mut unknown = BTreeSet::new();
+        let mut anomalies = vec![];
+        for (i, line) in s.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if line.is_empty() {
+                continue;
+            }
+            let trees = trees.get_or_insert_with(|| BitGrid::new(line.chars().count()));
+            let y = trees.push_row();
+            if let Some(terrain) = terrain.as_mut() {
+                terrain.resize(terrain.len() + trees.width(), 0);
+            }
+            let mut found = 0;
+            for (x, tile) in line.chars().enumerate() {
+                found += 1;
+                let item = legend.item(tile);
+                if item == ForestItem::Unknown {
+                    anomalies.push(ForestAnomaly::UnknownTile { line: i + 1, column: x + 1, tile });
+                }
+                if x >= trees.width() {
+                    continue;
+                }
+                match item {
+                    ForestItem::Tree => trees.set(x, y, true),
+                    ForestItem::Unknown => { unknown.insert(Point::new(x as i64, y as i64)); },
+                    ForestItem::Terrain(index) => {
+                        let width = trees.width();
+                        terrain.get_or_insert_with(|| vec![0; width * (y + 1)])[y * width + x] = index;
+                    },
+                    ForestItem::Open => {},
+                }
+            }
+            let expected = trees.width();
+            if found != expected {
+                anomalies.push(ForestAnomaly::RaggedRow { line: i + 1, expected, found });
+                unknown.extend((found..expected).map(|x| Point::new(x as i64, y as i64)));
+            }
+        }
+        let trees = trees.ok_or(ForestParseError(ForestAnomaly::Empty))?;
+        let terrain = terrain.map(|cells| Grid::from_cells(cells, trees.width(), Topology::Cylinder))
+            .transpose()
+            .expect("one terrain cell per square");
+        Ok((Forest { trees, unknown, terrain, legend }, anomalies))
+    }
 
-        Forest {
-            rows: rows.clone(),
-            width: rows[0].len(),
+    fn multiply_all(&self) -> usize {
+        self.evaluate_slopes(&PUZZLE_SLOPES)
+            .iter()
+            .map(|result| result.trees)
+            .product()
+    }
+    fn find_item(&self, pos: Point) -> ForestItem {
+        assert!(self.contains(pos), "position is outside of the forest");
+        let x = pos.x.rem_euclid(self.width() as i64);
+        let terrain = self.terrain.as_ref()
+            .and_then(|terrain| terrain.get(pos))
+            .map_or(0, |index| *index);
+        if self.trees.get(x as usize, pos.y as usize) {
+            ForestItem::Tree
+        } else if terrain != 0 {
+            ForestItem::Terrain(terrain)
+        } else if self.unknown.contains(&Point::new(x, pos.y)) {
+            ForestItem::Unknown
+        } else {
+            ForestItem::Open
         }
     }
 
-    fn find_item(&self, pos: Position) -> ForestItem {
-        self.rows[pos.y][pos.x % self.width]
+    fn row(&self, y : usize) -> impl Iterator<Item = ForestItem> + '_ {
+        (0..self.width() as i64).map(move |x| self.find_item(Point::new(x, y as i64)))
+    }
+
+    fn width(&self) -> usize {
+        self.trees.width()
     }
 
     fn height(&self) -> usize {
-        self.rows.len()
+        self.trees.height()
     }
 
-    fn contains(&self, pos : Position) -> bool {
-        pos.y < self.height()
+    fn contains(&self, pos : Point) -> bool {
+        pos.y >= 0 && pos.y < self.height() as i64
     }
 
-    fn how_many_trees_do_you_encounter(&self, slope : Position) -> usize {
-        let mut current = Position{x: 0, y: 0};
-        let mut trees = 0;
-        loop {
-            if !self.contains(current) {
-                break;
-            }
-            let item = self.find_item(current);
-            if item == ForestItem::Tree {
-                trees = trees + 1;
-            }
-            current = current + slope;
-        }
+    //positions visited from the top left corner, a horizontal slope stops once it has gone all the way around
+    fn path(&self, slope : Point) -> impl Iterator<Item = Point> + '_ {
+        let steps = if slope.y != 0 {
+            usize::MAX
+        } else if slope.x == 0 {
+            1
+        } else {
+            let width = self.width() as i64;
+            (width / gcd(slope.x.abs(), width)) as usize
+        };
+        std::iter::successors(Some(Point::ORIGIN), move |current| Some(*current + slope))
+            .take_while(move |current| self.contains(*current))
+            .take(steps)
+    }
+
+    fn how_many_trees_do_you_encounter(&self, slope : Point) -> usize {
+        self.path(slope)
+            .filter(|current| self.find_item(*current) == ForestItem::Tree)
+            .count()
+    }
+}
+fn gcd(a : i64, b : i64) -> i64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
 
-        trees
+//`--route --algorithm bfs|dijkstra|astar --from 0,0 --to-row N --avoid-trees` plans a route
+//with king moves, or with the given slopes as the only moves
+fn route(options : &Options, forest : &Forest, slopes : Vec<Point>) -> Result<(), Box<dyn std::error::Error>> {
+    let query = RouteQuery {
+        start: options.get("from").map(|from| parse_slope(from, forest.slope_limit())).transpose()?.unwrap_or(Point::ORIGIN),
+        goal_row: options.parse::<i64>("to-row")?.unwrap_or(forest.height() as i64 - 1),
+        moves: if slopes.is_empty() { MoveSet::King } else { MoveSet::Slopes(slopes) },
+        avoid_trees: options.has("avoid-trees"),
+    };
+    let algorithm = options.parse::<Algorithm>("algorithm")?.unwrap_or(Algorithm::AStar);
+    match forest.find_route(&query, algorithm) {
+        Some(route) => {
+            println!("route to row {}: {} trees hit in {} steps", query.goal_row, route.cost.trees, route.cost.steps);
+            let path : Vec<String> = route.path.iter().map(|p| format!("{},{}", p.x, p.y)).collect();
+            println!("{}", path.join(" "));
+        },
+        None => println!("row {} is unreachable", query.goal_row),
     }
+    Ok(())
 }
+
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::from_env();
     let contents = read_input(3)?;
 
-    let forest = Forest::from_str(contents.as_str());
+    //`--legend path` adds terrains and their costs, one `symbol name cost` line each
+    let legend = match options.get("legend") {
+        Some(path) => {
+            let legend = Legend::from_str(fs::read_to_string(path)?.as_str())?;
+            println!("using legend:\n{}", legend);
+            legend
+        },
+        None => Legend::default(),
+    };
+    //`--lenient` reports anomalies in the map instead of stopping at the first one
+    let forest = if options.has("lenient") {
+        let (forest, anomalies) = Forest::parse_with_legend(contents.as_str(), legend)?;
+        for anomaly in &anomalies {
+            println!("warning: {}", anomaly);
+        }
+        forest
+    } else {
+        Forest::parse_strict(contents.as_str(), legend)?
+    };
     //println!("{:?}", forest);
 
-    let trees = forest.how_many_trees_do_you_encounter(Position{x:3, y: 1});
+    //`--slopes 1,1 3,1` and `--slopes-file path` evaluate the given slopes together
+    let mut slopes = options.get_all("slopes").into_iter()
+        .map(|slope| parse_slope(slope, forest.slope_limit()))
+        .collect::<Result<Vec<Point>, _>>()?;
+    if let Some(path) = options.get("slopes-file") {
+        slopes.extend(parse_slopes(fs::read_to_string(path)?.as_str(), forest.slope_limit())?);
+    }
+    if options.has("route") {
+        return route(&options, &forest, slopes);
+    }
+    //`--render text|ppm|png --output path --scale N` draws the slopes, 3,1 by default
+    if let Some(format) = options.parse::<RenderFormat>("render")? {
+        if slopes.is_empty() {
+            slopes.push(Point::new(3, 1));
+        }
+        let scale = options.parse::<usize>("scale")?.unwrap_or(4);
+        let rendered = forest.render(&slopes, format, scale);
+        match options.get("output") {
+            Some(path) => {
+                println!("writing {:?} rendering of {} slope(s) to {:?}", format, slopes.len(), path);
+                fs::write(path, rendered)?;
+            },
+            None => io::stdout().write_all(&rendered)?,
+        }
+        return Ok(());
+    }
+    if !slopes.is_empty() {
+        println!("{}", SlopeTable(&forest.evaluate_slopes(&slopes)));
+        return Ok(());
+    }
+    //`--search 7,2 --objective most --top 5` ranks every slope up to 7 right or left and 2 down
+    if let Some(bound) = options.get("search") {
+        let bound = parse_slope(bound, forest.slope_limit())?;
+        let objective = options.parse::<Objective>("objective")?.unwrap_or(Objective::FewestTrees);
+        let top = options.parse::<usize>("top")?.unwrap_or(10);
+        let mut results = forest.search_slopes(bound.x, bound.y, objective);
+        results.truncate(top);
+        println!("{}", SlopeTable(&results));
+        return Ok(());
+    }
+
+    let trees = forest.how_many_trees_do_you_encounter(Point::new(3, 1));
     println!("trees hit: {}", trees);
 
     println!("all trees hit, multiplied: {}", forest.multiply_all());
@@ -124,7 +336,7 @@ mod tests {
     use super::*;
     use ForestItem::*;
 
-    const FOREST_SAMPLE :&str= "..##.......
+    pub(super) const FOREST_SAMPLE :&str= "..##.......
 #...#...#..
 .#....#..#.
 ..#.#...#.#
@@ -137,9 +349,9 @@ mod tests {
 .#..#...#.#";
     #[test]
     fn parse_forest() {
-        assert_eq!(Forest::from_str(FOREST_SAMPLE), Forest {
-            width: 11,
-            rows: vec!(
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        assert_eq!(forest.width(), 11);
+        assert_eq!((0..forest.height()).map(|y| forest.row(y).collect()).collect::<Vec<Vec<ForestItem>>>(), vec!(
                 vec!(Open,Open,Tree,Tree,Open,Open,Open,Open,Open,Open,Open),
                 vec!(Tree,Open,Open,Open,Tree,Open,Open,Open,Tree,Open,Open),
                 vec!(Open,Tree,Open,Open,Open,Open,Tree,Open,Open,Tree,Open),
@@ -151,30 +363,150 @@ mod tests {
                 vec!(Tree,Open,Tree,Tree,Open,Open,Open,Tree,Open,Open,Open),
                 vec!(Tree,Open,Open,Open,Tree,Tree,Open,Open,Open,Open,Tree),
                 vec!(Open,Tree,Open,Open,Tree,Open,Open,Open,Tree,Open,Tree),
-            )
-        })
+        ));
+        assert_eq!(forest.to_string(), FOREST_SAMPLE);
     }
 
     #[test]
     fn forest_get_item() {
-        let forest = Forest::from_str("..##.\n#..##");
-        assert_eq!(forest.find_item(Position{x: 0, y: 0}), Open);
-        assert_eq!(forest.find_item(Position{x: 0, y: 1}), Tree);
+        let forest = Forest::from_str("..##.\n#..##").unwrap();
+        assert_eq!(forest.find_item(Point::new(0, 0)), Open);
+        assert_eq!(forest.find_item(Point::new(0, 1)), Tree);
 
         //repeating
-        assert_eq!(forest.find_item(Position{x: 5, y: 0}), Open);
-        assert_eq!(forest.find_item(Position{x: 5, y: 1}), Tree);
+        assert_eq!(forest.find_item(Point::new(5, 0)), Open);
+        assert_eq!(forest.find_item(Point::new(5, 1)), Tree);
     }
 
     #[test]
     fn check_slope() {
-        let forest = Forest::from_str(FOREST_SAMPLE);
-        assert_eq!(7, forest.how_many_trees_do_you_encounter(Position{x: 3, y: 1}));
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        assert_eq!(7, forest.how_many_trees_do_you_encounter(Point::new(3, 1)));
     }
 
     #[test]
     fn check_slopes() {
-        let forest = Forest::from_str(FOREST_SAMPLE);
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
         assert_eq!(336, forest.multiply_all());
     }
+
+    #[test]
+    fn forest_get_item_to_the_left() {
+        let forest = Forest::from_str("..##.\n#..##").unwrap();
+        assert_eq!(forest.find_item(Point::new(-1, 0)), Open);
+        assert_eq!(forest.find_item(Point::new(-1, 1)), Tree);
+        assert_eq!(forest.find_item(Point::new(-7, 0)), Tree);
+    }
+
+    //column x of the mirror holds column -x of the original, so going left in one is going right in the other
+    fn mirror(s : &str) -> String {
+        s.split('\n')
+            .map(|row| {
+                let chars : Vec<char> = row.chars().collect();
+                (0..chars.len()).map(|x| chars[(chars.len() - x) % chars.len()]).collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[rstest(dx, dy,
+        case(1, 1),
+        case(3, 1),
+        case(5, 1),
+        case(7, 1),
+        case(1, 2),
+        case(13, 3),
+        ::trace
+    )]
+    fn leftward_slopes(dx : i64, dy : i64) {
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        let mirrored = Forest::from_str(mirror(FOREST_SAMPLE).as_str()).unwrap();
+        assert_eq!(forest.how_many_trees_do_you_encounter(Point::new(-dx, dy)),
+            mirrored.how_many_trees_do_you_encounter(Point::new(dx, dy)));
+    }
+
+    #[test]
+    fn leftward_slope() {
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        assert_eq!(forest.how_many_trees_do_you_encounter(Point::new(-3, 1)), 3);
+    }
+
+    #[test]
+    fn degenerate_slopes() {
+        let forest = Forest::from_str("#.#.\n....").unwrap();
+        //upwards leaves the forest straight away, after checking the start
+        assert_eq!(forest.how_many_trees_do_you_encounter(Point::new(1, -1)), 1);
+        //sideways goes around the row once
+        assert_eq!(forest.how_many_trees_do_you_encounter(Point::new(1, 0)), 2);
+        assert_eq!(forest.how_many_trees_do_you_encounter(Point::new(-2, 0)), 2);
+        assert_eq!(forest.how_many_trees_do_you_encounter(Point::new(0, 0)), 1);
+    }
+
+    #[rstest(input, expected,
+        case("", ForestAnomaly::Empty),
+        case("\n\r\n", ForestAnomaly::Empty),
+        case("..#\n.x#", ForestAnomaly::UnknownTile { line: 2, column: 2, tile: 'x' }),
+        case("..#\n\n.#", ForestAnomaly::RaggedRow { line: 3, expected: 3, found: 2 }),
+        case("..#\n.#..", ForestAnomaly::RaggedRow { line: 2, expected: 3, found: 4 }),
+        ::trace
+    )]
+    fn strict_parse_errors(input : &str, expected : ForestAnomaly) {
+        assert_eq!(Forest::from_str(input), Err(ForestParseError(expected)));
+    }
+
+    #[test]
+    fn strict_parse_error_message() {
+        assert_eq!(Forest::from_str("..#\r\n.é#").unwrap_err().to_string(),
+            "Unable to parse forest: unknown tile 'é' at line 2, column 2");
+    }
+
+    #[test]
+    fn lenient_parse_lists_anomalies() {
+        let (forest, anomalies) = Forest::parse_lenient("..#\n?.#.\n#\n.%#").unwrap();
+        assert_eq!(anomalies, vec![
+            ForestAnomaly::UnknownTile { line: 2, column: 1, tile: '?' },
+            ForestAnomaly::RaggedRow { line: 2, expected: 3, found: 4 },
+            ForestAnomaly::RaggedRow { line: 3, expected: 3, found: 1 },
+            ForestAnomaly::UnknownTile { line: 4, column: 2, tile: '%' },
+        ]);
+        assert_eq!(forest.to_string(), "..#\n?.#\n#??\n.?#");
+        //unknown tiles are open ground
+        assert_eq!(forest.how_many_trees_do_you_encounter(Point::new(2, 1)), 1);
+        assert_eq!(Forest::parse_lenient("\n").unwrap_err(), ForestParseError(ForestAnomaly::Empty));
+        assert_eq!(Forest::parse_lenient(FOREST_SAMPLE).unwrap(), (Forest::from_str(FOREST_SAMPLE).unwrap(), vec![]));
+    }
+
+    #[test]
+    fn large_forest_is_bit_packed() {
+        let rows = 100_000;
+        let input : String = (0..rows).map(|y| if y % 3 == 0 { "..#....#.......................\n" } else { "...............................\n" }).collect();
+        let forest = Forest::from_str(input.as_str()).unwrap();
+        assert_eq!((forest.width(), forest.height()), (31, rows));
+        //a single word per row of 31 squares
+        assert_eq!(forest.trees.memory(), rows * 8);
+        assert_eq!(forest.trees.count_ones(), 2 * (rows + 2) / 3);
+        assert!(forest.unknown.is_empty());
+        for result in forest.evaluate_slopes(&[Point::new(0, 3), Point::new(2, 3), Point::new(7, 1)]) {
+            assert_eq!(result.trees, forest.how_many_trees_do_you_encounter(result.slope));
+        }
+        assert_eq!(forest.how_many_trees_do_you_encounter(Point::new(0, 3)), 0);
+    }
+
+    #[test]
+    fn weighted_terrain() {
+        let legend = Legend::from_str("* snow 2\n~ ice 5").unwrap();
+        let map = ".*~#\n~*#.\n..*~";
+        let forest = Forest::parse_strict(map, legend.clone()).unwrap();
+        assert_eq!(forest.to_string(), map);
+        assert_eq!(forest.find_item(Point::new(1, 0)), Terrain(2));
+        assert_eq!(forest.find_item(Point::new(-1, 2)), Terrain(3));
+        assert_eq!(forest.find_item(Point::new(3, 0)), Tree);
+        //snow, snow along 1,1 and a single tree along 2,1
+        let results = forest.evaluate_slopes(&[Point::new(1, 1), Point::new(2, 1)]);
+        assert_eq!(results.iter().map(|result| (result.trees, result.cost)).collect::<Vec<(usize, u64)>>(), vec![(0, 4), (1, 1)]);
+        assert_eq!(Forest::parse_strict(map, Legend::default()),
+            Err(ForestParseError(ForestAnomaly::UnknownTile { line: 1, column: 2, tile: '*' })));
+        //maps of trees and open ground don't carry a terrain layer
+        assert_eq!(Forest::parse_strict(FOREST_SAMPLE, legend).unwrap().terrain, None);
+    }
 }
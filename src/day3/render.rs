@@ -0,0 +1,184 @@
+use super::{Forest, ForestItem};
+use crate::geometry::Point;
+use crate::image::{Image, Rgb};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum RenderFormat {
+    Text,
+    Ppm,
+    Png,
+}
+
+#[derive(Debug)]
+pub struct RenderFormatParseError;
+
+impl std::error::Error for RenderFormatParseError {}
+impl fmt::Display for RenderFormatParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse render format, expected one of text, ppm or png")
+    }
+}
+
+impl FromStr for RenderFormat {
+    type Err = RenderFormatParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(RenderFormat::Text),
+            "ppm" => Ok(RenderFormat::Ppm),
+            "png" => Ok(RenderFormat::Png),
+            _ => Err(RenderFormatParseError),
+        }
+    }
+}
+
+const OPEN_COLOR : Rgb = [236, 240, 241];
+const TREE_COLOR : Rgb = [39, 110, 59];
+const UNKNOWN_COLOR : Rgb = [128, 128, 128];
+//other terrains from the legend, in the order they were given
+const TERRAIN_COLORS : [Rgb; 4] = [
+    [214, 234, 248],
+    [174, 214, 241],
+    [149, 165, 166],
+    [220, 198, 160],
+];
+//one color per slope, trees hit are drawn in a darker shade
+const SLOPE_COLORS : [Rgb; 6] = [
+    [231, 76, 60],
+    [52, 152, 219],
+    [241, 196, 15],
+    [155, 89, 182],
+    [230, 126, 34],
+    [26, 188, 156],
+];
+
+fn darker(color : Rgb) -> Rgb {
+    [color[0] / 2, color[1] / 2, color[2] / 2]
+}
+
+impl Forest {
+    //squares visited by each slope, keyed by their position before wrapping, the first slope wins where paths cross
+    fn marks(&self, slopes : &[Point]) -> HashMap<Point, usize> {
+        let mut marks = HashMap::new();
+        for (i, slope) in slopes.iter().enumerate() {
+            for current in self.path(*slope) {
+                marks.entry(current).or_insert(i);
+            }
+        }
+        marks
+    }
+
+    //the map repeated horizontally until every mark fits
+    fn rendered_columns(&self, marks : &HashMap<Point, usize>) -> std::ops::Range<i64> {
+        let width = self.width() as i64;
+        let first = marks.keys().map(|p| p.x.div_euclid(width)).min().unwrap_or(0).min(0);
+        let last = marks.keys().map(|p| p.x.div_euclid(width)).max().unwrap_or(0).max(0);
+        first * width..(last + 1) * width
+    }
+
+    //the puzzle's picture, O for open squares and X for trees along the paths
+    pub fn render_text(&self, slopes : &[Point]) -> String {
+        let marks = self.marks(slopes);
+        let columns = self.rendered_columns(&marks);
+        let mut out = String::new();
+        for y in 0..self.height() as i64 {
+            if y > 0 {
+                out.push('\n');
+            }
+            for x in columns.clone() {
+                let p = Point::new(x, y);
+                let item = self.find_item(p);
+                out.push(match (marks.contains_key(&p), item) {
+                    (true, ForestItem::Tree) => 'X',
+                    (true, _) => 'O',
+                    (false, item) => self.legend.symbol(item),
+                });
+            }
+        }
+        out
+    }
+
+    //each square becomes a block of scale x scale pixels
+    pub fn render_image(&self, slopes : &[Point], scale : usize) -> Image {
+        let marks = self.marks(slopes);
+        let columns = self.rendered_columns(&marks);
+        let scale = scale.max(1);
+        let mut image = Image::new((columns.end - columns.start) as usize * scale, self.height() * scale, OPEN_COLOR);
+        for y in 0..self.height() as i64 {
+            for (column, x) in columns.clone().enumerate() {
+                let p = Point::new(x, y);
+                let item = self.find_item(p);
+                let color = match (marks.get(&p), item) {
+                    (Some(i), ForestItem::Tree) => darker(SLOPE_COLORS[i % SLOPE_COLORS.len()]),
+                    (Some(i), _) => SLOPE_COLORS[i % SLOPE_COLORS.len()],
+                    (None, ForestItem::Tree) => TREE_COLOR,
+                    (None, ForestItem::Open) => continue,
+                    (None, ForestItem::Terrain(index)) => TERRAIN_COLORS[index as usize % TERRAIN_COLORS.len()],
+                    (None, ForestItem::Unknown) => UNKNOWN_COLOR,
+                };
+                image.fill(column * scale, y as usize * scale, scale, scale, color);
+            }
+        }
+        image
+    }
+
+    pub fn render(&self, slopes : &[Point], format : RenderFormat, scale : usize) -> Vec<u8> {
+        match format {
+            RenderFormat::Text => {
+                let mut text = self.render_text(slopes);
+                text.push('\n');
+                text.into_bytes()
+            },
+            RenderFormat::Ppm => self.render_image(slopes, scale).to_ppm(),
+            RenderFormat::Png => self.render_image(slopes, scale).to_png(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL_FOREST : &str = "..##.\n#..##\n.#..#";
+
+    #[test]
+    fn rightward_path() {
+        let forest = Forest::from_str(SMALL_FOREST).unwrap();
+        assert_eq!(forest.render_text(&[Point::new(3, 1)]), "O.##...##.
+#..X##..##
+.#..#.X..#");
+    }
+
+    #[test]
+    fn leftward_path() {
+        let forest = Forest::from_str(SMALL_FOREST).unwrap();
+        assert_eq!(forest.render_text(&[Point::new(-1, 1)]), "..##.O.##.
+#..#X#..##
+.#.O#.#..#");
+    }
+
+    #[test]
+    fn no_slopes_is_the_plain_map() {
+        let forest = Forest::from_str(SMALL_FOREST).unwrap();
+        assert_eq!(forest.render_text(&[]), SMALL_FOREST);
+        assert_eq!(forest.to_string(), SMALL_FOREST);
+    }
+
+    #[test]
+    fn one_color_per_slope() {
+        let forest = Forest::from_str(SMALL_FOREST).unwrap();
+        let image = forest.render_image(&[Point::new(3, 1), Point::new(1, 1)], 2);
+        assert_eq!((image.width(), image.height()), (20, 6));
+        //both paths start in the corner, the first slope is drawn there
+        assert_eq!(image.get(1, 1), SLOPE_COLORS[0]);
+        assert_eq!(image.get(7, 3), darker(SLOPE_COLORS[0]));
+        assert_eq!(image.get(2, 2), SLOPE_COLORS[1]);
+        assert_eq!(image.get(4, 0), TREE_COLOR);
+        assert_eq!(image.get(2, 0), OPEN_COLOR);
+        assert!(forest.render(&[Point::new(3, 1)], RenderFormat::Png, 1).starts_with(b"\x89PNG"));
+        assert!(forest.render(&[Point::new(3, 1)], RenderFormat::Ppm, 1).starts_with(b"P6\n10 3\n"));
+    }
+}
@@ -0,0 +1,281 @@
+use super::{Forest, ForestItem};
+use crate::geometry::Point;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::ops::Add;
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+#[cfg(test)]
+use rstest::rstest;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Algorithm {
+    //fewest steps, whatever the trees
+    Bfs,
+    //fewest trees, then fewest steps
+    Dijkstra,
+    //same as Dijkstra, guided by the rows left to the goal
+    AStar,
+}
+
+#[derive(Debug)]
+pub struct AlgorithmParseError;
+
+impl Error for AlgorithmParseError {}
+impl fmt::Display for AlgorithmParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse algorithm, expected one of bfs, dijkstra or astar")
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = AlgorithmParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s {
+            "bfs" => Ok(Algorithm::Bfs),
+            "dijkstra" => Ok(Algorithm::Dijkstra),
+            "astar" => Ok(Algorithm::AStar),
+            _ => Err(AlgorithmParseError),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MoveSet {
+    Slopes(Vec<Point>),
+    //one square in any of the eight directions
+    King,
+}
+
+impl MoveSet {
+    fn moves(&self) -> &[Point] {
+        match self {
+            MoveSet::Slopes(slopes) => slopes,
+            MoveSet::King => &Point::DIRECTIONS_8,
+        }
+    }
+}
+
+//trees hit come first, so a longer route through open ground beats a short one through a tree
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default)]
+pub struct Cost {
+    pub trees : usize,
+    pub steps : usize,
+}
+
+impl Add for Cost {
+    type Output = Cost;
+
+    fn add(self, other : Cost) -> Cost {
+        Cost { trees: self.trees + other.trees, steps: self.steps + other.steps }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Route {
+    //squares visited from the start, before wrapping around the forest
+    pub path : Vec<Point>,
+    pub cost : Cost,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RouteQuery {
+    pub start : Point,
+    pub goal_row : i64,
+    pub moves : MoveSet,
+    //trees become impassable instead of costly
+    pub avoid_trees : bool,
+}
+
+impl Forest {
+    fn square_cost(&self, p : Point) -> Cost {
+        Cost { trees: (self.find_item(p) == ForestItem::Tree) as usize, steps: 0 }
+    }
+
+    fn passable(&self, p : Point, avoid_trees : bool) -> bool {
+        self.contains(p) && !(avoid_trees && self.find_item(p) == ForestItem::Tree)
+    }
+
+    //fewest steps needed to reach the goal row, never more than the real cost
+    fn rows_left(query : &RouteQuery, p : Point) -> Cost {
+        let distance = (query.goal_row - p.y).abs();
+        let reach = query.moves.moves().iter()
+            .map(|m| m.y.abs())
+            .max()
+            .unwrap_or(0);
+        let steps = if reach == 0 { 0 } else { (distance + reach - 1) / reach };
+        Cost { trees: 0, steps: steps as usize }
+    }
+
+    //the start square counts towards the cost, like it does for slopes; None when the goal row can't be reached
+    pub fn find_route(&self, query : &RouteQuery, algorithm : Algorithm) -> Option<Route> {
+        if !self.passable(query.start, query.avoid_trees) || !self.contains(Point::new(0, query.goal_row)) {
+            return None;
+        }
+        let width = self.width() as i64;
+        let wrap = |p : Point| Point::new(p.x.rem_euclid(width), p.y);
+        let start_cost = self.square_cost(query.start);
+
+        //best known cost per wrapped square, with the square and move it was reached from
+        let mut best : HashMap<Point, (Cost, Option<(Point, Point)>)> = HashMap::new();
+        best.insert(wrap(query.start), (start_cost, None));
+        let mut queue = VecDeque::new();
+        let mut heap = BinaryHeap::new();
+        match algorithm {
+            Algorithm::Bfs => queue.push_back(wrap(query.start)),
+            _ => heap.push(Reverse((start_cost + Forest::rows_left(query, query.start), start_cost, wrap(query.start)))),
+        }
+
+        let goal = loop {
+            let (current, cost) = match algorithm {
+                Algorithm::Bfs => {
+                    let current = queue.pop_front()?;
+                    (current, best[&current].0)
+                },
+                _ => {
+                    let Reverse((_, cost, current)) = heap.pop()?;
+                    if cost > best[&current].0 {
+                        continue;
+                    }
+                    (current, cost)
+                },
+            };
+            if current.y == query.goal_row {
+                break current;
+            }
+            for m in query.moves.moves() {
+                let next = wrap(current + *m);
+                if !self.passable(next, query.avoid_trees) {
+                    continue;
+                }
+                let next_cost = cost + self.square_cost(next) + Cost { trees: 0, steps: 1 };
+                let improves = match best.get(&next) {
+                    None => true,
+                    //breadth first reaches every square in the fewest steps the first time round
+                    Some(_) if algorithm == Algorithm::Bfs => false,
+                    Some((known, _)) => next_cost < *known,
+                };
+                if improves {
+                    best.insert(next, (next_cost, Some((current, *m))));
+                    match algorithm {
+                        Algorithm::Bfs => queue.push_back(next),
+                        Algorithm::Dijkstra => heap.push(Reverse((next_cost, next_cost, next))),
+                        Algorithm::AStar => heap.push(Reverse((next_cost + Forest::rows_left(query, next), next_cost, next))),
+                    }
+                }
+            }
+        };
+
+        let mut moves = vec![];
+        let mut current = goal;
+        while let Some((previous, m)) = best[&current].1 {
+            moves.push(m);
+            current = previous;
+        }
+        let path = moves.iter().rev()
+            .scan(query.start, |p, m| {
+                *p += *m;
+                Some(*p)
+            });
+        Some(Route {
+            path: std::iter::once(query.start).chain(path).collect(),
+            cost: best[&goal].0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::FOREST_SAMPLE;
+
+    fn query(goal_row : i64, moves : MoveSet, avoid_trees : bool) -> RouteQuery {
+        RouteQuery { start: Point::ORIGIN, goal_row, moves, avoid_trees }
+    }
+
+    fn trees_on(forest : &Forest, route : &Route) -> usize {
+        route.path.iter().filter(|p| forest.find_item(**p) == ForestItem::Tree).count()
+    }
+
+    //every step of the path is one of the allowed moves and the cost matches the squares visited
+    fn check_route(forest : &Forest, query : &RouteQuery, route : &Route) {
+        assert_eq!(route.path[0], query.start);
+        assert_eq!(route.path.last().unwrap().y, query.goal_row);
+        for pair in route.path.windows(2) {
+            assert!(query.moves.moves().contains(&(pair[1] - pair[0])), "{:?}", pair);
+        }
+        assert_eq!(route.cost, Cost { trees: trees_on(forest, route), steps: route.path.len() - 1 });
+        if query.avoid_trees {
+            assert_eq!(route.cost.trees, 0);
+        }
+    }
+
+    #[rstest(avoid_trees, case(false), case(true), ::trace)]
+    fn king_moves_agree(avoid_trees : bool) {
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        let query = query(10, MoveSet::King, avoid_trees);
+        let dijkstra = forest.find_route(&query, Algorithm::Dijkstra).unwrap();
+        let astar = forest.find_route(&query, Algorithm::AStar).unwrap();
+        let bfs = forest.find_route(&query, Algorithm::Bfs).unwrap();
+        for route in &[&dijkstra, &astar, &bfs] {
+            check_route(&forest, &query, route);
+        }
+        assert_eq!(dijkstra.cost, astar.cost);
+        assert_eq!(dijkstra.cost, Cost { trees: 0, steps: 10 });
+        assert_eq!(bfs.cost.steps, 10);
+    }
+
+    #[test]
+    fn fewest_trees_along_slopes() {
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        let query = query(10, MoveSet::Slopes(vec![Point::new(3, 1), Point::new(1, 1)]), false);
+        let route = forest.find_route(&query, Algorithm::Dijkstra).unwrap();
+        check_route(&forest, &query, &route);
+        assert!(route.cost.trees <= forest.how_many_trees_do_you_encounter(Point::new(1, 1)));
+        assert_eq!(forest.find_route(&query, Algorithm::AStar).unwrap().cost, route.cost);
+        assert!(forest.find_route(&query, Algorithm::Bfs).unwrap().cost.trees >= route.cost.trees);
+    }
+
+    #[test]
+    fn detour_around_trees() {
+        let forest = Forest::from_str(".#.\n.#.\n...\n##.\n.#.").unwrap();
+        let query = query(4, MoveSet::King, true);
+        let route = forest.find_route(&query, Algorithm::AStar).unwrap();
+        check_route(&forest, &query, &route);
+        //the only gap in the fourth row is in the last column
+        assert_eq!(route.path[3].x.rem_euclid(3), 2);
+        assert_eq!(route.cost, Cost { trees: 0, steps: 4 });
+        let costly = forest.find_route(&RouteQuery { avoid_trees: false, ..query.clone() }, Algorithm::Dijkstra).unwrap();
+        assert_eq!(costly.cost, route.cost);
+        //straight down through the trees is shorter when only steps count
+        let bfs = forest.find_route(&RouteQuery { avoid_trees: false, ..query }, Algorithm::Bfs).unwrap();
+        assert_eq!(bfs.cost.steps, 4);
+    }
+
+    #[test]
+    fn unreachable_goals() {
+        let forest = Forest::from_str("...\n###\n...").unwrap();
+        for algorithm in &[Algorithm::Bfs, Algorithm::Dijkstra, Algorithm::AStar] {
+            assert_eq!(forest.find_route(&query(2, MoveSet::King, true), *algorithm), None);
+            //outside of the forest
+            assert_eq!(forest.find_route(&query(3, MoveSet::King, false), *algorithm), None);
+            //no move goes down
+            assert_eq!(forest.find_route(&query(2, MoveSet::Slopes(vec![Point::new(1, 0)]), false), *algorithm), None);
+            //already there
+            let route = forest.find_route(&query(0, MoveSet::King, false), *algorithm).unwrap();
+            assert_eq!(route, Route { path: vec![Point::ORIGIN], cost: Cost::default() });
+        }
+        let through = forest.find_route(&query(2, MoveSet::King, false), Algorithm::Dijkstra).unwrap();
+        assert_eq!(through.cost, Cost { trees: 1, steps: 2 });
+        let start_on_tree = RouteQuery { start: Point::new(1, 1), ..query(2, MoveSet::King, true) };
+        assert_eq!(forest.find_route(&start_on_tree, Algorithm::AStar), None);
+    }
+
+    #[test]
+    fn parse_algorithms() {
+        assert_eq!("astar".parse::<Algorithm>().unwrap(), Algorithm::AStar);
+        assert!("greedy".parse::<Algorithm>().is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use super::{Forest, ForestItem};
+use crate::geometry::Point;
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SlopeResult {
+    pub slope : Point,
+    pub trees : usize,
+    //sum of the legend costs of the squares visited
+    pub cost : u64,
+    //squares visited, including the starting one
+    pub squares : usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Objective {
+    FewestTrees,
+    MostTrees,
+    LowestCost,
+    HighestCost,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SlopeParseError {
+    input : String,
+    reason : String,
+}
+
+impl Error for SlopeParseError {}
+impl fmt::Display for SlopeParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse slope {:?}, {}", self.input, self.reason)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ObjectiveParseError {
+    input : String,
+}
+
+impl Error for ObjectiveParseError {}
+impl fmt::Display for ObjectiveParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse objective {:?}, expected fewest, most, cheapest or costliest", self.input)
+    }
+}
+
+//`right,down`, so `3,1` is three right and one down and `-1,2` goes left,
+//neither part may go past the limit in either direction
+pub fn parse_slope(s : &str, limit : Point) -> Result<Point, SlopeParseError> {
+    let error = || SlopeParseError { input: s.to_string(), reason: "expected right,down such as 3,1".to_string() };
+    let mut tokens = s.split(',').map(str::trim);
+    let x = tokens.next().ok_or_else(error)?.parse::<i64>().map_err(|_| error())?;
+    let y = tokens.next().ok_or_else(error)?.parse::<i64>().map_err(|_| error())?;
+    if tokens.next().is_some() {
+        return Err(error());
+    }
+    if x.abs() > limit.x || y.abs() > limit.y {
+        return Err(SlopeParseError {
+            input: s.to_string(),
+            reason: format!("expected at most {} right or left and {} up or down", limit.x, limit.y),
+        });
+    }
+    Ok(Point::new(x, y))
+}
+
+//one slope per line, blank lines and lines starting with # are ignored
+pub fn parse_slopes(s : &str, limit : Point) -> Result<Vec<Point>, SlopeParseError> {
+    s.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| parse_slope(line, limit))
+        .collect()
+}
+
+impl FromStr for Objective {
+    type Err = ObjectiveParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s {
+            "fewest" => Ok(Objective::FewestTrees),
+            "most" => Ok(Objective::MostTrees),
+            "cheapest" => Ok(Objective::LowestCost),
+            "costliest" => Ok(Objective::HighestCost),
+            _ => Err(ObjectiveParseError { input: s.to_string() }),
+        }
+    }
+}
+
+impl Forest {
+    //steeper slopes only repeat shallower ones or leave the forest, and keeping them out
+    //keeps every step times slope well inside an i64
+    pub fn slope_limit(&self) -> Point {
+        Point::new(self.width() as i64, self.height() as i64)
+    }
+
+    //evaluates every downward slope in one pass over the rows, other slopes are walked one by one
+    pub fn evaluate_slopes(&self, slopes : &[Point]) -> Vec<SlopeResult> {
+        let mut results : Vec<SlopeResult> = slopes.iter()
+            .map(|slope| SlopeResult { slope: *slope, trees: 0, cost: 0, squares: 0 })
+            .collect();
+        //open ground and trees only, so the tree bits alone tell every square apart
+        let plain = self.terrain.is_none() && self.unknown.is_empty();
+        let width = self.width() as i64;
+        for y in 0..self.height() {
+            //a row without trees only adds open squares
+            let any_trees = self.trees.row_words(y).iter().any(|word| *word != 0);
+            for result in results.iter_mut().filter(|result| result.slope.y > 0) {
+                if y as i64 % result.slope.y != 0 {
+                    continue;
+                }
+                let x = y as i64 / result.slope.y * result.slope.x;
+                if !plain {
+                    self.visit(result, Point::new(x, y as i64));
+                } else if any_trees && self.trees.get(x.rem_euclid(width) as usize, y) {
+                    self.count(result, ForestItem::Tree);
+                } else {
+                    self.count(result, ForestItem::Open);
+                }
+            }
+        }
+        for result in results.iter_mut().filter(|result| result.slope.y <= 0) {
+            for current in self.path(result.slope) {
+                self.visit(result, current);
+            }
+        }
+        results
+    }
+
+    fn visit(&self, result : &mut SlopeResult, p : Point) {
+        self.count(result, self.find_item(p));
+    }
+
+    fn count(&self, result : &mut SlopeResult, item : ForestItem) {
+        result.squares += 1;
+        result.cost += self.legend.cost(item);
+        if item == ForestItem::Tree {
+            result.trees += 1;
+        }
+    }
+
+    //every slope up to max_right in either direction and max_down downwards, best first
+    pub fn search_slopes(&self, max_right : i64, max_down : i64, objective : Objective) -> Vec<SlopeResult> {
+        let slopes : Vec<Point> = (1..=max_down)
+            .flat_map(|y| (-max_right..=max_right).map(move |x| Point::new(x, y)))
+            .collect();
+        let mut results = self.evaluate_slopes(&slopes);
+        results.sort_by(|a, b| {
+            let by_objective = match objective {
+                Objective::FewestTrees => a.trees.cmp(&b.trees),
+                Objective::MostTrees => b.trees.cmp(&a.trees),
+                Objective::LowestCost => a.cost.cmp(&b.cost),
+                Objective::HighestCost => b.cost.cmp(&a.cost),
+            };
+            //on a tie the longer ride wins, then the gentler slope
+            by_objective
+                .then(b.squares.cmp(&a.squares))
+                .then(a.slope.manhattan(Point::ORIGIN).cmp(&b.slope.manhattan(Point::ORIGIN)))
+                .then(a.slope.cmp(&b.slope))
+        });
+        results
+    }
+}
+
+//ranked table of slope results
+pub struct SlopeTable<'a>(pub &'a [SlopeResult]);
+
+impl<'a> fmt::Display for SlopeTable<'a> {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>4}  {:>8}  {:>6}  {:>6}  {:>7}", "rank", "slope", "trees", "cost", "squares")?;
+        let mut rank = 0;
+        let mut previous : Option<&SlopeResult> = None;
+        for (i, result) in self.0.iter().enumerate() {
+            //equal results share a rank
+            if previous.map(|p| (p.trees, p.cost, p.squares).cmp(&(result.trees, result.cost, result.squares))) != Some(Ordering::Equal) {
+                rank = i + 1;
+            }
+            previous = Some(result);
+            write!(f, "\n{:>4}  {:>8}  {:>6}  {:>6}  {:>7}",
+                rank, format!("{},{}", result.slope.x, result.slope.y), result.trees, result.cost, result.squares)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::FOREST_SAMPLE;
+    use super::super::terrain::Legend;
+    use super::super::PUZZLE_SLOPES;
+
+    const LIMIT : Point = Point::new(11, 11);
+
+    #[test]
+    fn parse_slopes_from_text() {
+        assert_eq!(parse_slope("3,1", LIMIT), Ok(Point::new(3, 1)));
+        assert_eq!(parse_slope(" -1 , 2 ", LIMIT), Ok(Point::new(-1, 2)));
+        assert!(parse_slope("3", LIMIT).is_err());
+        assert!(parse_slope("3,1,2", LIMIT).is_err());
+        assert_eq!(parse_slope("a,1", LIMIT).unwrap_err().to_string(), "Unable to parse slope \"a,1\", expected right,down such as 3,1");
+        assert_eq!(parse_slopes("# puzzle slopes\n1,1\n\n3,1\n", LIMIT), Ok(vec![Point::new(1, 1), Point::new(3, 1)]));
+    }
+
+    #[test]
+    fn slopes_stay_within_the_forest() {
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        assert_eq!(forest.slope_limit(), LIMIT);
+        assert_eq!(parse_slope("-11,11", LIMIT), Ok(Point::new(-11, 11)));
+        //this used to overflow when multiplied by the step
+        let huge = format!("{},1", i64::MAX);
+        assert_eq!(parse_slope(&huge, LIMIT).unwrap_err().to_string(),
+            format!("Unable to parse slope {:?}, expected at most 11 right or left and 11 up or down", huge));
+        assert!(parse_slope("0,-12", LIMIT).is_err());
+        assert!(parse_slopes("1,1\n12,1", LIMIT).is_err());
+    }
+
+    #[test]
+    fn parse_objectives() {
+        assert_eq!(Objective::from_str("cheapest"), Ok(Objective::LowestCost));
+        assert_eq!(Objective::from_str("safest").unwrap_err().to_string(),
+            "Unable to parse objective \"safest\", expected fewest, most, cheapest or costliest");
+    }
+
+    #[test]
+    fn single_pass_matches_walking() {
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        let slopes : Vec<Point> = (-12..=12)
+            .flat_map(|x| (-2..=4).map(move |y| Point::new(x, y)))
+            .collect();
+        for result in forest.evaluate_slopes(&slopes) {
+            assert_eq!(result.trees, forest.how_many_trees_do_you_encounter(result.slope), "{:?}", result.slope);
+            assert_eq!(result.squares, forest.path(result.slope).count(), "{:?}", result.slope);
+        }
+    }
+
+    #[test]
+    fn puzzle_slopes() {
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        let trees : Vec<usize> = forest.evaluate_slopes(&parse_slopes("1,1\n3,1\n5,1\n7,1\n1,2", LIMIT).unwrap())
+            .iter()
+            .map(|result| result.trees)
+            .collect();
+        assert_eq!(trees, vec![2, 7, 3, 4, 2]);
+    }
+
+    #[test]
+    fn search_for_safest_and_riskiest() {
+        let forest = Forest::from_str(FOREST_SAMPLE).unwrap();
+        let safest = forest.search_slopes(3, 1, Objective::FewestTrees);
+        assert_eq!(safest.len(), 7);
+        assert_eq!(safest[0], SlopeResult { slope: Point::new(2, 1), trees: 1, cost: 1, squares: 11 });
+        assert!(safest.windows(2).all(|pair| pair[0].trees <= pair[1].trees));
+
+        let riskiest = forest.search_slopes(3, 1, Objective::MostTrees);
+        assert_eq!(riskiest[0], SlopeResult { slope: Point::new(3, 1), trees: 7, cost: 7, squares: 11 });
+    }
+
+    #[test]
+    fn ranked_table() {
+        let results = [
+            SlopeResult { slope: Point::new(-2, 1), trees: 1, cost: 1, squares: 11 },
+            SlopeResult { slope: Point::new(2, 1), trees: 1, cost: 1, squares: 11 },
+            SlopeResult { slope: Point::new(10, 1), trees: 3, cost: 3, squares: 11 },
+        ];
+        assert_eq!(SlopeTable(&results).to_string(), "rank     slope   trees    cost  squares
+   1      -2,1       1       1       11
+   1       2,1       1       1       11
+   3      10,1       3       3       11");
+    }
+
+    #[test]
+    fn plain_maps_agree_with_walking() {
+        let legend = Legend::from_str("# tree 3\n. open 1").unwrap();
+        let forest = Forest::parse_strict(FOREST_SAMPLE, legend).unwrap();
+        assert!(forest.terrain.is_none() && forest.unknown.is_empty());
+        for result in forest.evaluate_slopes(&PUZZLE_SLOPES) {
+            let mut walked = SlopeResult { slope: result.slope, trees: 0, cost: 0, squares: 0 };
+            for current in forest.path(result.slope) {
+                forest.visit(&mut walked, current);
+            }
+            assert_eq!(result, walked);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use super::ForestItem;
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Terrain {
+    pub symbol : char,
+    pub name : String,
+    pub cost : u64,
+}
+
+//the terrain for each map character, `.` is always open ground and `#` always a tree
+#[derive(Debug, PartialEq, Clone)]
+pub struct Legend {
+    //open ground first, then trees, then everything else
+    terrains : Vec<Terrain>,
+}
+
+const OPEN : usize = 0;
+const TREE : usize = 1;
+
+impl Default for Legend {
+    fn default() -> Legend {
+        Legend {
+            terrains: vec![
+                Terrain { symbol: '.', name: "open".to_string(), cost: 0 },
+                Terrain { symbol: '#', name: "tree".to_string(), cost: 1 },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LegendParseError {
+    line : usize,
+    message : String,
+}
+
+impl Error for LegendParseError {}
+impl fmt::Display for LegendParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse legend at line {}: {}", self.line, self.message)
+    }
+}
+
+//one `symbol name cost` entry per line, such as `~ ice 2`, on top of the default legend
+impl FromStr for Legend {
+    type Err = LegendParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        let mut legend = Legend::default();
+        for (i, line) in s.lines().enumerate() {
+            let error = |message : &str| LegendParseError { line: i + 1, message: message.to_string() };
+            let tokens : Vec<&str> = line.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            let (symbol, name, cost) = match tokens.as_slice() {
+                [symbol, name, cost] => (*symbol, *name, *cost),
+                _ => return Err(error("expected a symbol, a name and a cost")),
+            };
+            let mut chars = symbol.chars();
+            let symbol = match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => return Err(error("symbols are a single character")),
+            };
+            let cost = cost.parse::<u64>().map_err(|_| error("costs are whole numbers"))?;
+            let terrain = Terrain { symbol, name: name.to_string(), cost };
+            match legend.terrains.iter().position(|t| t.symbol == symbol) {
+                Some(index) => legend.terrains[index] = terrain,
+                None if legend.terrains.len() > u8::MAX as usize => return Err(error("too many terrains")),
+                None => legend.terrains.push(terrain),
+            }
+        }
+        Ok(legend)
+    }
+}
+
+impl Legend {
+    pub fn item(&self, symbol : char) -> ForestItem {
+        match self.terrains.iter().position(|t| t.symbol == symbol) {
+            Some(OPEN) => ForestItem::Open,
+            Some(TREE) => ForestItem::Tree,
+            Some(index) => ForestItem::Terrain(index as u8),
+            None => ForestItem::Unknown,
+        }
+    }
+
+    fn terrain(&self, item : ForestItem) -> Option<&Terrain> {
+        match item {
+            ForestItem::Open => Some(&self.terrains[OPEN]),
+            ForestItem::Tree => Some(&self.terrains[TREE]),
+            ForestItem::Terrain(index) => self.terrains.get(index as usize),
+            ForestItem::Unknown => None,
+        }
+    }
+
+    pub fn symbol(&self, item : ForestItem) -> char {
+        self.terrain(item).map_or('?', |t| t.symbol)
+    }
+
+    //unknown squares are crossed like open ground
+    pub fn cost(&self, item : ForestItem) -> u64 {
+        self.terrain(item).map_or(self.terrains[OPEN].cost, |t| t.cost)
+    }
+}
+
+impl fmt::Display for Legend {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        for (i, terrain) in self.terrains.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{} {} {}", terrain.symbol, terrain.name, terrain.cost)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_legend() {
+        let legend = Legend::default();
+        assert_eq!(legend.item('.'), ForestItem::Open);
+        assert_eq!(legend.item('#'), ForestItem::Tree);
+        assert_eq!(legend.item('~'), ForestItem::Unknown);
+        assert_eq!((legend.symbol(ForestItem::Tree), legend.cost(ForestItem::Tree)), ('#', 1));
+        assert_eq!((legend.symbol(ForestItem::Unknown), legend.cost(ForestItem::Unknown)), ('?', 0));
+    }
+
+    #[test]
+    fn custom_legend() {
+        let legend = Legend::from_str("* snow 2\n~ ice 1\n\n^ rock 5\n# tree 10\n~ ice 3").unwrap();
+        assert_eq!(legend.item('*'), ForestItem::Terrain(2));
+        assert_eq!(legend.item('^'), ForestItem::Terrain(4));
+        assert_eq!(legend.item('.'), ForestItem::Open);
+        assert_eq!(legend.cost(legend.item('~')), 3);
+        assert_eq!(legend.cost(ForestItem::Tree), 10);
+        assert_eq!(legend.symbol(ForestItem::Terrain(4)), '^');
+        assert_eq!(legend.to_string(), ". open 0\n# tree 10\n* snow 2\n~ ice 3\n^ rock 5");
+        assert_eq!(Legend::from_str(legend.to_string().as_str()), Ok(legend));
+    }
+
+    #[test]
+    fn legend_errors() {
+        assert_eq!(Legend::from_str("* snow").unwrap_err().to_string(),
+            "Unable to parse legend at line 1: expected a symbol, a name and a cost");
+        assert_eq!(Legend::from_str("\n** snow 1").unwrap_err().to_string(),
+            "Unable to parse legend at line 2: symbols are a single character");
+        assert_eq!(Legend::from_str("* snow -1").unwrap_err().to_string(),
+            "Unable to parse legend at line 1: costs are whole numbers");
+    }
+}
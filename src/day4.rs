@@ -1,12 +1,34 @@
+mod convert;
+mod duplicates;
+mod profile;
+mod query;
+mod redact;
+mod repair;
+mod report;
+mod schema;
+mod typed;
+
 use crate::inputs::read_input;
+use crate::options::Options;
+use crate::siphash::SipKey;
+use convert::BatchFormat;
+use profile::Profile;
+use query::{PassportStore, Query};
+use redact::{Redaction, RedactionError, PASSPHRASE_VAR};
+use report::ValidationSummary;
+use schema::Schema;
+use typed::TypedPassport;
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
 use std::str::FromStr;
 use std::{error::Error, fmt};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[cfg(test)]
 use rstest::rstest;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Default)]
 struct Passport {
     birth_year: Option<String>,
     issue_year: Option<String>,
@@ -16,135 +38,154 @@ struct Passport {
     eye_color: Option<String>,
     passport_id: Option<String>,
     country_id: Option<String>,
+    //any other key, so schemas can declare fields of their own
+    extra: BTreeMap<String, String>,
+}
+
+//problems with a record's tokens, `record` and `line` count from 1
+#[derive(Debug, PartialEq, Clone)]
+enum PassportAnomaly {
+    MalformedToken { record : usize, line : usize, token : String },
+    DuplicateKey { record : usize, line : usize, key : String, first : String, second : String },
+    UnknownKey { record : usize, line : usize, key : String },
+}
+
+impl fmt::Display for PassportAnomaly {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PassportAnomaly::MalformedToken { record, line, token } =>
+                write!(f, "record {}, line {}: malformed token {:?}, expected key:value", record, line, token),
+            PassportAnomaly::DuplicateKey { record, line, key, first, second } =>
+                write!(f, "record {}, line {}: duplicate key {} ({} and {})", record, line, key, first, second),
+            PassportAnomaly::UnknownKey { record, line, key } =>
+                write!(f, "record {}, line {}: unknown key {}", record, line, key),
+        }
+    }
 }
 
-#[derive(Debug)]
-struct PassportParseError;
+#[derive(Debug, PartialEq)]
+struct PassportParseError(PassportAnomaly);
 
 impl Error for PassportParseError {}
 impl fmt::Display for PassportParseError {
     fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Unable to parse passport")
+        write!(f, "Unable to parse passport: {}", self.0)
     }
 }
 
 impl Passport {
-    fn validate_byr(s : &str) -> bool {
-        match s.parse::<i32>() {
-            Ok(i) => i >= 1920 && i <= 2002,
-                _ => false,
-        }
-    }
-    fn validate_iyr(s : &str) -> bool {
-        match s.parse::<i32>() {
-            Ok(i) => i >= 2010 && i <= 2020,
-                _ => false,
-        }
+    fn get(&self, key : &str) -> Option<&str> {
+        let field = match key {
+            "byr" => &self.birth_year,
+            "iyr" => &self.issue_year,
+            "eyr" => &self.expiration_year,
+            "hgt" => &self.height,
+            "hcl" => &self.hair_color,
+            "ecl" => &self.eye_color,
+            "pid" => &self.passport_id,
+            "cid" => &self.country_id,
+            _ => return self.extra.get(key).map(String::as_str),
+        };
+        field.as_deref()
     }
-    fn validate_eyr(s : &str) -> bool {
-        match s.parse::<i32>() {
-            Ok(i) => i >= 2020 && i <= 2030,
-                _ => false,
-        }
+
+    fn set(&mut self, key : &str, value : &str) {
+        let field = match key {
+            "byr" => &mut self.birth_year,
+            "iyr" => &mut self.issue_year,
+            "eyr" => &mut self.expiration_year,
+            "hgt" => &mut self.height,
+            "hcl" => &mut self.hair_color,
+            "ecl" => &mut self.eye_color,
+            "pid" => &mut self.passport_id,
+            "cid" => &mut self.country_id,
+            _ => {
+                self.extra.insert(key.to_string(), value.to_string());
+                return;
+            },
+        };
+        *field = Some(value.to_string());
     }
-    fn validate_hgt(s : &str) -> bool {
-        if s.ends_with("cm") {
-            match s[0..(s.len() - 2)].parse::<i32>() {
-                Ok(cm) => cm >= 150 && cm <= 193,
-                _ => false,
-            }
-        } else if s.ends_with("in") {
-            match s[0..(s.len() - 2)].parse::<i32>() {
-                Ok(inches) => inches >= 59 && inches <= 76,
-                _ => false,
+}
+
+const KNOWN_KEYS : [&str; 8] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid", "cid"];
+
+//the known keys in puzzle order, then any others by name, all on one line
+impl fmt::Display for Passport {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        let known = KNOWN_KEYS.iter().filter_map(|key| self.get(key).map(|value| (*key, value)));
+        let extra = self.extra.iter().map(|(key, value)| (key.as_str(), value.as_str()));
+        for (i, (key, value)) in known.chain(extra).enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
             }
-        } else {
-            false
+            write!(f, "{}:{}", key, value)?;
         }
+        Ok(())
     }
-    fn is_hex(c : char) -> bool {
-        match c {
-            '0'..='9' => true,
-            'A'..='F' => true,
-            'a'..='f' => true,
-            _ => false,
-        }
+}
+
+//collects one record's fields, whatever format they come from, and reports the same problems for all of them
+struct RecordBuilder<'a> {
+    record : usize,
+    is_known : &'a dyn Fn(&str) -> bool,
+    passport : Passport,
+    anomalies : Vec<PassportAnomaly>,
+}
+
+impl<'a> RecordBuilder<'a> {
+    fn new(record : usize, is_known : &'a dyn Fn(&str) -> bool) -> RecordBuilder<'a> {
+        RecordBuilder { record, is_known, passport: Passport::default(), anomalies: vec![] }
     }
 
-    fn validate_hcl(s : &str) -> bool {
-        if s.len() != 7 || !s.starts_with("#") {
-            false
-        } else {
-            s[1..s.len()].chars().map(Passport::is_hex).fold(true, |a, b| a && b)
+    //only fields that can be written back as a single `key:value` token are kept
+    fn add(&mut self, line : usize, key : &str, value : &str) {
+        let record = self.record;
+        if key.is_empty() || value.is_empty() || key.contains(':') || key.contains(char::is_whitespace) || value.contains(char::is_whitespace) {
+            self.anomalies.push(PassportAnomaly::MalformedToken { record, line, token: format!("{}:{}", key, value) });
+            return;
         }
-    }
-    fn validate_ecl(s : &str) -> bool {
-        vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth"].contains(&s)
-    }
-    fn validate_pid(s : &str) -> bool {
-        if s.len() != 9 {
-            false
-        } else {
-            s.chars().map(char::is_numeric).fold(true, |a, b| a && b)
+        //the last value wins, as it always has
+        if let Some(first) = self.passport.get(key) {
+            self.anomalies.push(PassportAnomaly::DuplicateKey {
+                record, line, key: key.to_string(), first: first.to_string(), second: value.to_string(),
+            });
+        } else if !(self.is_known)(key) {
+            self.anomalies.push(PassportAnomaly::UnknownKey { record, line, key: key.to_string() });
         }
+        self.passport.set(key, value);
     }
-    fn is_valid_part1(&self) -> bool {
-        let fields = vec![
-            self.birth_year.as_ref(),
-            self.issue_year.as_ref(),
-            self.expiration_year.as_ref(),
-            self.height.as_ref(),
-            self.hair_color.as_ref(),
-            self.eye_color.as_ref(),
-            self.passport_id.as_ref(),
-            //no cid is ok as that means its from the north pole
-            //self.cid.as_ref(),
-        ];
-        fields.iter()
-            .map(|x| x.is_some())
-            .fold(true, |a, b| a && b)
-    }
-
-    fn is_valid_part2(&self) -> bool {
-        if !self.is_valid_part1() {
-            false
-        } else {
-        let fields : Vec<(Option<&String>, fn(&str) -> bool)> = vec![
-            (self.birth_year.as_ref(), Passport::validate_byr),
-            (self.issue_year.as_ref(), Passport::validate_iyr),
-            (self.expiration_year.as_ref(), Passport::validate_eyr),
-            (self.height.as_ref(), Passport::validate_hgt),
-            (self.hair_color.as_ref(), Passport::validate_hcl),
-            (self.eye_color.as_ref(), Passport::validate_ecl),
-            (self.passport_id.as_ref(), Passport::validate_pid)
-        ];
-        fields.iter()
-            .map( |(field, validator)|  match field {
-                Some(s) => validator(s.as_str()),
-                None => false,
-            })
-        .fold(true, |a, b| a && b)
+
+    fn finish(self) -> (Passport, Vec<PassportAnomaly>) {
+        (self.passport, self.anomalies)
+    }
+}
+
+//the record's lines, each with its line number in the whole input
+fn parse_record(record : usize, lines : &[(usize, &str)], is_known : &dyn Fn(&str) -> bool) -> (Passport, Vec<PassportAnomaly>) {
+    let mut builder = RecordBuilder::new(record, is_known);
+    for &(line, text) in lines {
+        for token in text.split_whitespace() {
+            match token.split_once(':') {
+                Some((key, value)) => builder.add(line, key, value),
+                None => builder.anomalies.push(PassportAnomaly::MalformedToken { record, line, token: token.to_string() }),
+            }
         }
     }
+    builder.finish()
 }
+
+//any key is accepted here, only a schema can tell which keys are unknown
 impl FromStr for Passport {
     type Err = PassportParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let tokens : HashMap<&str, &str> = s.split_whitespace()
-            .map(|x| x.split(":").collect::<Vec<&str>>())
-            .map(|x| (x[0], x[1]))
-            .collect();
-        Ok(Passport{
-            birth_year: tokens.get("byr").map(|x| x.to_string()),
-            issue_year: tokens.get("iyr").map(|x| x.to_string()),
-            expiration_year: tokens.get("eyr").map(|x| x.to_string()),
-            height: tokens.get("hgt").map(|x| x.to_string()),
-            hair_color: tokens.get("hcl").map(|x| x.to_string()),
-            eye_color: tokens.get("ecl").map(|x| x.to_string()),
-            passport_id: tokens.get("pid").map(|x| x.to_string()),
-            country_id: tokens.get("cid").map(|x| x.to_string()),
-        })
+        let lines : Vec<(usize, &str)> = s.lines().enumerate().map(|(i, line)| (i + 1, line)).collect();
+        let (passport, anomalies) = parse_record(1, &lines, &|_| true);
+        match anomalies.into_iter().next() {
+            Some(anomaly) => Err(PassportParseError(anomaly)),
+            None => Ok(passport),
+        }
     }
 }
 
@@ -154,18 +195,24 @@ struct Passports {
 }
 
 impl Passports {
-    fn valid_passports_part1(&self) -> usize {
+    fn valid_passports_part1(&self, schema : &Schema) -> usize {
         self.passports.iter()
-            .filter(|passport| passport.is_valid_part1())
-            .collect::<Vec<&Passport>>()
-            .len()
+            .filter(|passport| schema.has_required_fields(passport))
+            .count()
     }
 
-    fn valid_passports_part2(&self) -> usize {
+    fn valid_passports_part2(&self, schema : &Schema) -> usize {
         self.passports.iter()
-            .filter(|passport| passport.is_valid_part2())
-            .collect::<Vec<&Passport>>()
-            .len()
+            .filter(|passport| schema.is_valid(passport))
+            .count()
+    }
+
+    fn summary(&self, schema : &Schema) -> ValidationSummary {
+        let mut summary = ValidationSummary::default();
+        for passport in &self.passports {
+            summary.record(&schema.validate(passport));
+        }
+        summary
     }
 
     #[cfg(test)]
@@ -175,52 +222,207 @@ impl Passports {
 
     #[cfg(test)]
     fn find_by_id(&self, id : &str) -> Option<&Passport> {
-        let matches : Vec<&Passport> = self.passports.iter()
-            .filter(|passport| match &passport.passport_id {
-                None => false,
-                Some(passport_id) => *passport_id == id.to_string(),
-            })
-            .collect();
-        if matches.len() == 0 {
-            None
-        } else {
-            Some(matches[0])
+        PassportStore::new(self, &Schema::default()).find_by_id(id)
+    }
+}
+//records separated by blank lines, each in the canonical order
+impl fmt::Display for Passports {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        for (i, passport) in self.passports.iter().enumerate() {
+            if i > 0 {
+                write!(f, "\n\n")?;
+            }
+            write!(f, "{}", passport)?;
         }
+        Ok(())
     }
 }
+
 impl FromStr for Passports {
     type Err = PassportParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Passports::parse_strict(s, &Schema::default())
+    }
+}
+
+impl Passports {
+    //keeps going past problems, keys the schema doesn't declare are kept but reported
+    fn parse_with_schema(s : &str, schema : &Schema) -> (Passports, Vec<PassportAnomaly>) {
         let mut passports : Vec<Passport> = vec![];
+        let mut anomalies = vec![];
 
-        let mut current : Vec<String> = vec![];
+        let mut current : Vec<(usize, &str)> = vec![];
 
-        for line in s.split("\n") {
-            if line.is_empty() {
-                let passport = Passport::from_str(current.join("\n").as_str())?;
+        //the extra empty line ends the last record
+        for (i, line) in s.split('\n').chain(std::iter::once("")).enumerate() {
+            if !line.is_empty() {
+                current.push((i + 1, line));
+            } else if !current.is_empty() {
+                let (passport, found) = parse_record(passports.len() + 1, &current, &|key| schema.declares(key));
                 passports.push(passport);
+                anomalies.extend(found);
                 current.clear();
-            } else {
-                current.push(line.to_string());
             }
         }
-        if current.len() > 0 {
-            let passport = Passport::from_str(current.join("\n").as_str())?;
-            passports.push(passport);
+        (Passports { passports }, anomalies)
+    }
+
+    fn parse_strict(s : &str, schema : &Schema) -> Result<Passports, PassportParseError> {
+        let (passports, anomalies) = Passports::parse_with_schema(s, schema);
+        match anomalies.into_iter().next() {
+            Some(anomaly) => Err(PassportParseError(anomaly)),
+            None => Ok(passports),
         }
-        Ok(Passports{
-            passports: passports,
-        })
     }
 }
+
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let contents = read_input(4)?;
+    let options = Options::from_env();
+
+    //`--schema path` swaps the puzzle's rules for other ones, `--profile name` applies one of the named variations on top
+    let base = match options.get("schema") {
+        Some(path) => Schema::from_str(fs::read_to_string(path)?.as_str())?,
+        None => Schema::default(),
+    };
+    let schema = match options.parse::<Profile>("profile")? {
+        Some(profile) => profile.apply(base.clone()),
+        None => base.clone(),
+    };
+    //`--input path` reads another batch, `--from jsonl|csv` one in another format
+    let contents = match options.get("input") {
+        Some(path) => fs::read_to_string(path)?,
+        None => read_input(4)?,
+    };
+    //`--lenient` reports malformed tokens, duplicate and unknown keys instead of stopping at the first one
+    let format = options.parse::<BatchFormat>("from")?.unwrap_or(BatchFormat::Records);
+    let (passports, anomalies) = Passports::import(contents.as_str(), format, &schema)?;
+    if options.has("lenient") {
+        for anomaly in &anomalies {
+            println!("warning: {}", anomaly);
+        }
+    } else if let Some(anomaly) = anomalies.into_iter().next() {
+        return Err(Box::new(PassportParseError(anomaly)));
+    }
+    //`--redact` replaces pid and cid with pseudonyms for everything below, keyed by the passphrase in
+    //DAY4_REDACT_PASSPHRASE or in `--passphrase-file path`, `--year-buckets N` also rounds years down
+    let passports = if options.has("redact") {
+        if options.get("redact").is_some() {
+            return Err(Box::new(RedactionError::PassphraseOnCommandLine));
+        }
+        let passphrase = match options.get("passphrase-file") {
+            Some(path) => fs::read_to_string(path)?.trim_end_matches(&['\r', '\n'][..]).to_string(),
+            None => env::var(PASSPHRASE_VAR).unwrap_or_default(),
+        };
+        if passphrase.is_empty() {
+            return Err(Box::new(RedactionError::MissingPassphrase));
+        }
+        let redaction = Redaction { key: SipKey::from_passphrase(&passphrase), year_bucket: options.parse::<i64>("year-buckets")? };
+        passports.redacted(&redaction, &schema)?
+    } else {
+        passports
+    };
+
+    //`--convert records|jsonl|csv --output path` writes the batch out, `--verdicts` adds whether each passport is valid and why not
+    if let Some(format) = options.parse::<BatchFormat>("convert")? {
+        let verdicts = if options.has("verdicts") { Some(&schema) } else { None };
+        let converted = passports.export(format, verdicts);
+        match options.get("output") {
+            Some(path) => {
+                println!("writing {} passports as {:?} to {:?}", passports.passports.len(), format, path);
+                fs::write(path, converted)?;
+            },
+            None => print!("{}", converted),
+        }
+        return Ok(());
+    }
+
+    //`--duplicates` clusters passports that share a pid or are at least `--similarity 0.8` alike
+    if options.has("duplicates") {
+        let threshold = options.parse::<f64>("similarity")?.unwrap_or(0.8);
+        let clusters = passports.duplicates(threshold);
+        for cluster in &clusters {
+            println!("{}", cluster);
+        }
+        println!("{} clusters of likely duplicates", clusters.len());
+        return Ok(());
+    }
 
-    let passports = Passports::from_str(contents.as_str())?;
+    //`--profiles` counts the passports each profile accepts
+    if options.has("profiles") {
+        println!("{}", passports.profile_counts(&base));
+        return Ok(());
+    }
 
-    println!("part1: found {} valid passports", passports.valid_passports_part1());
-    println!("part2: found {} valid passports", passports.valid_passports_part2());
+    //`--query byr>=1950 and ecl in (blu,grn) and not valid` lists the matching passports, ordered by `--sort key`,
+    //or counts them per value with `--group-by key`
+    if options.has("query") || options.has("group-by") {
+        let store = PassportStore::new(&passports, &schema);
+        let query = options.get_all("query").join(" ");
+        let matches = if query.is_empty() { store.all() } else { store.select(&Query::from_str(query.as_str())?) };
+        if let Some(key) = options.get("group-by") {
+            for (value, count) in store.group_by(key, &matches) {
+                println!("{:>6}  {}", count, value);
+            }
+        } else {
+            let ordered = match options.get("sort") {
+                Some(key) => store.sorted(&matches, key),
+                None => matches.iter().copied().collect(),
+            };
+            for i in ordered {
+                println!("passport {}: {}", i + 1, store.passport(i));
+            }
+        }
+        println!("{} of {} passports match", matches.len(), passports.passports.len());
+        return Ok(());
+    }
+
+    //`--repairs` suggests fixes for every invalid passport, without applying them
+    if options.has("repairs") {
+        let mut invalid = 0;
+        let mut fixable = 0;
+        for (i, passport) in passports.passports.iter().enumerate() {
+            let repairs = schema.suggest_repairs(passport);
+            if repairs.report.is_valid() {
+                continue;
+            }
+            invalid += 1;
+            if repairs.is_fixable() {
+                fixable += 1;
+            }
+            println!("passport {}: {}", i + 1, repairs);
+        }
+        println!("{} of {} invalid passports have a suggestion for every invalid field", fixable, invalid);
+        return Ok(());
+    }
+
+    //`--report` counts rejections per reason, `--explain` also lists every rejected passport
+    if options.has("report") || options.has("explain") {
+        if options.has("explain") {
+            for (i, passport) in passports.passports.iter().enumerate() {
+                let report = schema.validate(passport);
+                if !report.is_valid() {
+                    println!("passport {}: {}", i + 1, report);
+                }
+            }
+        }
+        println!("{}", passports.summary(&schema));
+        return Ok(());
+    }
+
+    //`--canonical` writes every passport back out one per line, fields in the same order
+    if options.has("canonical") {
+        for (i, passport) in passports.passports.iter().enumerate() {
+            match TypedPassport::try_from(passport) {
+                Ok(typed) => println!("{}", typed),
+                Err(error) => println!("# passport {}: {}\n{}", i + 1, error, passport),
+            }
+        }
+        return Ok(());
+    }
+
+    println!("part1: found {} valid passports", passports.valid_passports_part1(&schema));
+    println!("part2: found {} valid passports", passports.valid_passports_part2(&schema));
 
     Ok(())
 }
@@ -256,6 +458,7 @@ byr:1937 iyr:2017 cid:147 hgt:183cm";
             eye_color: Some("gry".to_string()),
             passport_id: Some("860033327".to_string()),
             country_id: Some("147".to_string()),
+            extra: BTreeMap::new(),
         })
     }
 
@@ -272,6 +475,7 @@ byr:1937 iyr:2017 cid:147 hgt:183cm";
                     eye_color: Some("gry".to_string()),
                     passport_id: Some("860033327".to_string()),
                     country_id: Some("147".to_string()),
+                    extra: BTreeMap::new(),
                 },
                 Passport {
                     birth_year: Some("1929".to_string()),
@@ -282,6 +486,7 @@ byr:1937 iyr:2017 cid:147 hgt:183cm";
                     eye_color: Some("amb".to_string()),
                     passport_id: Some("028048884".to_string()),
                     country_id: Some("350".to_string()),
+                    extra: BTreeMap::new(),
                 },
                 Passport {
                     birth_year: Some("1931".to_string()),
@@ -292,6 +497,7 @@ byr:1937 iyr:2017 cid:147 hgt:183cm";
                     eye_color: Some("brn".to_string()),
                     passport_id: Some("760753108".to_string()),
                     country_id: None,
+                    extra: BTreeMap::new(),
                 },
                 Passport {
                     birth_year: None,
@@ -302,11 +508,37 @@ byr:1937 iyr:2017 cid:147 hgt:183cm";
                     eye_color: Some("brn".to_string()),
                     passport_id: Some("166559648".to_string()),
                     country_id: None,
+                    extra: BTreeMap::new(),
                 },
                 ],
         });
     }
 
+    #[test]
+    fn record_anomalies() {
+        let input = "byr:1937 iyr:2017\nhgt\n\n\npid:1 pid:2 zip:3 ecl:\nbyr:: :x";
+        let (passports, anomalies) = Passports::parse_with_schema(input, &Schema::default());
+        assert_eq!(anomalies.iter().map(PassportAnomaly::to_string).collect::<Vec<String>>(), vec![
+            "record 1, line 2: malformed token \"hgt\", expected key:value",
+            "record 2, line 5: duplicate key pid (1 and 2)",
+            "record 2, line 5: unknown key zip",
+            "record 2, line 5: malformed token \"ecl:\", expected key:value",
+            "record 2, line 6: malformed token \":x\", expected key:value",
+        ]);
+        assert_eq!(passports.len(), 2);
+        //the second pid replaces the first
+        assert_eq!(passports.passports[1].to_string(), "byr:: pid:2 zip:3");
+        assert_eq!(Passports::from_str(input).unwrap_err().to_string(),
+            "Unable to parse passport: record 1, line 2: malformed token \"hgt\", expected key:value");
+        //a schema that declares the key makes it known
+        let schema = Schema::from_str("zip optional digits 1").unwrap();
+        assert_eq!(Passports::parse_strict("zip:3", &schema).unwrap().len(), 1);
+        assert!(Passport::from_str("zip:3").is_ok());
+        assert_eq!(Passport::from_str("cid:1\ncid:1").unwrap_err(), PassportParseError(PassportAnomaly::DuplicateKey {
+            record: 1, line: 2, key: "cid".to_string(), first: "1".to_string(), second: "1".to_string(),
+        }));
+    }
+
     mod part1 {
         use super::*;
         #[rstest(passport_id, is_valid,
@@ -319,14 +551,14 @@ byr:1937 iyr:2017 cid:147 hgt:183cm";
             fn validate_passports_part1(passport_id : &str, is_valid : bool) {
                 let passports = Passports::from_str(PASSPORTS_SAMPLE).unwrap();
 
-                assert_eq!(passports.find_by_id(passport_id).unwrap().is_valid_part1(), is_valid);
+                assert_eq!(Schema::default().has_required_fields(passports.find_by_id(passport_id).unwrap()), is_valid);
             }
 
         #[test]
         fn valid_passport_count() {
             let passports = Passports::from_str(PASSPORTS_SAMPLE).unwrap();
 
-            assert_eq!(passports.valid_passports_part1(), 2);
+            assert_eq!(passports.valid_passports_part1(&Schema::default()), 2);
         }
     }
 
@@ -346,7 +578,9 @@ byr:1937 iyr:2017 cid:147 hgt:183cm";
                                 let input = $value;
                                 let is_valid = stringify!($valid).starts_with("valid");
 
-                                assert_eq!(Passport::[<validate_ $field>](input), is_valid);
+                                let schema = Schema::default();
+
+                                assert_eq!(schema.field(stringify!($field)).unwrap().kind.check(input), is_valid);
                             }
                         )*
                     }
@@ -391,7 +625,7 @@ pid:3556412378 byr:2007";
         fn invalid_passports() {
             let passports = Passports::from_str(INVALID_PART2_PASSPORTS).unwrap();
             assert_eq!(passports.len(), 4);
-            assert_eq!(passports.valid_passports_part2(), 0);
+            assert_eq!(passports.valid_passports_part2(&Schema::default()), 0);
         }
 
         const VALID_PART2_PASSPORTS : &str = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
@@ -411,7 +645,24 @@ iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719";
         fn valid_passports() {
             let passports = Passports::from_str(VALID_PART2_PASSPORTS).unwrap();
             assert_eq!(passports.len(), 4);
-            assert_eq!(passports.valid_passports_part2(), 4);
+            assert_eq!(passports.valid_passports_part2(&Schema::default()), 4);
+        }
+
+        #[test]
+        fn explain_rejections() {
+            let passports = Passports::from_str(INVALID_PART2_PASSPORTS).unwrap();
+            let schema = Schema::default();
+            let reports : Vec<String> = passports.passports.iter().map(|passport| schema.validate(passport).to_string()).collect();
+            assert_eq!(reports, vec![
+                "eyr=1972: must be 2020..=2030; hgt=170: must end in cm or in; pid=186cm: must be exactly 9 digits",
+                "eyr=1967: must be 2020..=2030",
+                "hcl=dab227: must be # followed by six lowercase hex digits",
+                "byr=2007: must be 1920..=2002; iyr=2023: must be 2010..=2020; eyr=2038: must be 2020..=2030; hgt=59cm: must be 150..=193 cm; hcl=74454a: must be # followed by six lowercase hex digits; ecl=zzz: must be one of amb, blu, brn, gry, grn, hzl, oth; pid=3556412378: must be exactly 9 digits",
+            ]);
+            let summary = passports.summary(&schema);
+            assert_eq!((summary.passports, summary.valid), (4, 0));
+            assert_eq!(summary.failures.get("eyr: must be 2020..=2030"), Some(&3));
+            assert_eq!(summary.failures.get("hcl: must be # followed by six lowercase hex digits"), Some(&2));
         }
     }
 }
@@ -0,0 +1,263 @@
+use super::{PassportAnomaly, Passports, RecordBuilder, KNOWN_KEYS};
+use super::schema::Schema;
+use crate::{csv, json};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BatchFormat {
+    //blank line separated `key:value` records, as in the puzzle input
+    Records,
+    JsonLines,
+    Csv,
+}
+
+#[derive(Debug)]
+pub struct BatchFormatParseError;
+
+impl Error for BatchFormatParseError {}
+impl fmt::Display for BatchFormatParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse batch format, expected one of records, jsonl or csv")
+    }
+}
+
+impl FromStr for BatchFormat {
+    type Err = BatchFormatParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        match s {
+            "records" => Ok(BatchFormat::Records),
+            "jsonl" => Ok(BatchFormat::JsonLines),
+            "csv" => Ok(BatchFormat::Csv),
+            _ => Err(BatchFormatParseError),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ImportError {
+    line : usize,
+    message : String,
+}
+
+impl Error for ImportError {}
+impl fmt::Display for ImportError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to import passports at line {}: {}", self.line, self.message)
+    }
+}
+
+//written with `--verdicts` and skipped on import, so passports can't use these keys
+const VALID_KEY : &str = "valid";
+const REASONS_KEY : &str = "reasons";
+
+impl Passports {
+    //the puzzle's keys first, then every other key any passport has
+    fn columns(&self) -> Vec<&str> {
+        let extra : BTreeSet<&str> = self.passports.iter()
+            .flat_map(|passport| passport.extra.keys().map(String::as_str))
+            .collect();
+        KNOWN_KEYS.iter().copied().chain(extra).collect()
+    }
+
+    //one object per line, every column present with missing fields as null
+    pub fn to_json_lines(&self, verdicts : Option<&Schema>) -> String {
+        let columns = self.columns();
+        let mut out = String::new();
+        for passport in &self.passports {
+            out.push('{');
+            for (i, key) in columns.iter().enumerate() {
+                let value = passport.get(key).map_or("null".to_string(), json::string);
+                let _ = write!(out, "{}{}:{}", if i > 0 { "," } else { "" }, json::string(key), value);
+            }
+            if let Some(schema) = verdicts {
+                let report = schema.validate(passport);
+                let reasons : Vec<String> = report.reasons().map(|reason| json::string(&reason)).collect();
+                let _ = write!(out, ",{}:{},{}:[{}]", json::string(VALID_KEY), report.is_valid(), json::string(REASONS_KEY), reasons.join(","));
+            }
+            out.push_str("}\n");
+        }
+        out
+    }
+
+    //a header row, then one row per passport with missing fields left empty
+    pub fn to_csv(&self, verdicts : Option<&Schema>) -> String {
+        let columns = self.columns();
+        let mut header = columns.clone();
+        if verdicts.is_some() {
+            header.extend(&[VALID_KEY, REASONS_KEY]);
+        }
+        let mut out = csv::row(&header);
+        out.push('\n');
+        for passport in &self.passports {
+            let mut values : Vec<String> = columns.iter()
+                .map(|key| passport.get(key).unwrap_or("").to_string())
+                .collect();
+            if let Some(schema) = verdicts {
+                let report = schema.validate(passport);
+                values.push(report.is_valid().to_string());
+                values.push(report.reasons().collect::<Vec<String>>().join("; "));
+            }
+            out.push_str(&csv::row(&values.iter().map(String::as_str).collect::<Vec<&str>>()));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn export(&self, format : BatchFormat, verdicts : Option<&Schema>) -> String {
+        match format {
+            BatchFormat::Records => format!("{}\n", self),
+            BatchFormat::JsonLines => self.to_json_lines(verdicts),
+            BatchFormat::Csv => self.to_csv(verdicts),
+        }
+    }
+
+    //the same passports and anomalies as the records would give, only the layout can fail the import
+    pub fn import(s : &str, format : BatchFormat, schema : &Schema) -> Result<(Passports, Vec<PassportAnomaly>), ImportError> {
+        match format {
+            BatchFormat::Records => Ok(Passports::parse_with_schema(s, schema)),
+            BatchFormat::JsonLines => Passports::from_json_lines(s, schema),
+            BatchFormat::Csv => Passports::from_csv(s, schema),
+        }
+    }
+
+    pub fn from_json_lines(s : &str, schema : &Schema) -> Result<(Passports, Vec<PassportAnomaly>), ImportError> {
+        let is_known = |key : &str| schema.declares(key);
+        let mut passports = vec![];
+        let mut anomalies = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let error = |message : String| ImportError { line: i + 1, message };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entries = match json::parse(line).map_err(|e| error(e.to_string()))? {
+                json::Value::Object(entries) => entries,
+                _ => return Err(error("expected an object".to_string())),
+            };
+            let mut builder = RecordBuilder::new(passports.len() + 1, &is_known);
+            for (key, value) in &entries {
+                match value {
+                    _ if key == VALID_KEY || key == REASONS_KEY => {},
+                    json::Value::Null => {},
+                    json::Value::String(value) => builder.add(i + 1, key, value),
+                    _ => return Err(error(format!("{} should be a string or null", key))),
+                }
+            }
+            let (passport, found) = builder.finish();
+            passports.push(passport);
+            anomalies.extend(found);
+        }
+        Ok((Passports { passports }, anomalies))
+    }
+
+    pub fn from_csv(s : &str, schema : &Schema) -> Result<(Passports, Vec<PassportAnomaly>), ImportError> {
+        let is_known = |key : &str| schema.declares(key);
+        let rows = csv::parse(s).map_err(|e| ImportError { line: e.line, message: e.message })?;
+        let mut rows = rows.into_iter();
+        let header = match rows.next() {
+            Some((_, header)) => header,
+            None => return Ok((Passports { passports: vec![] }, vec![])),
+        };
+        let mut passports = vec![];
+        let mut anomalies = vec![];
+        for (line, values) in rows {
+            if values.len() != header.len() {
+                return Err(ImportError { line, message: format!("expected {} fields, found {}", header.len(), values.len()) });
+            }
+            let mut builder = RecordBuilder::new(passports.len() + 1, &is_known);
+            for (key, value) in header.iter().zip(&values) {
+                if !value.is_empty() && key != VALID_KEY && key != REASONS_KEY {
+                    builder.add(line, key, value);
+                }
+            }
+            let (passport, found) = builder.finish();
+            passports.push(passport);
+            anomalies.extend(found);
+        }
+        Ok((Passports { passports }, anomalies))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE : &str = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929 note:\"a,b\"";
+
+    #[test]
+    fn json_lines_round_trip() {
+        let passports = Passports::from_str(SAMPLE.replace(" note:\"a,b\"", "").as_str()).unwrap();
+        let exported = passports.to_json_lines(None);
+        assert_eq!(exported.lines().nth(1).unwrap(),
+            r##"{"byr":"1929","iyr":"2013","eyr":"2023","hgt":null,"hcl":"#cfa07d","ecl":"amb","pid":"028048884","cid":"350"}"##);
+        assert_eq!(Passports::from_json_lines(exported.as_str(), &Schema::default()), Ok((passports, vec![])));
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let (passports, anomalies) = Passports::parse_with_schema(SAMPLE, &Schema::default());
+        let exported = passports.to_csv(None);
+        assert_eq!(exported, "byr,iyr,eyr,hgt,hcl,ecl,pid,cid,note
+1937,2017,2020,183cm,#fffffd,gry,860033327,147,
+1929,2013,2023,,#cfa07d,amb,028048884,350,\"\"\"a,b\"\"\"
+");
+        //the unknown note is reported as it is for the records, only on the line of the row
+        let (imported, found) = Passports::from_csv(exported.as_str(), &Schema::default()).unwrap();
+        assert_eq!(imported, passports);
+        assert_eq!(found, vec![PassportAnomaly::UnknownKey { record: 2, line: 3, key: "note".to_string() }]);
+        assert_eq!(anomalies, vec![PassportAnomaly::UnknownKey { record: 2, line: 5, key: "note".to_string() }]);
+        let records = passports.export(BatchFormat::Records, None);
+        assert_eq!(Passports::parse_with_schema(records.as_str(), &Schema::default()).0, passports);
+    }
+
+    #[test]
+    fn verdicts_are_attached_and_ignored_on_import() {
+        let passports = Passports::from_str(SAMPLE.replace(" note:\"a,b\"", "").as_str()).unwrap();
+        let schema = Schema::default();
+        let json = passports.to_json_lines(Some(&schema));
+        assert!(json.lines().nth(1).unwrap().ends_with(r#""cid":"350","valid":false,"reasons":["missing hgt"]}"#));
+        assert_eq!(Passports::from_json_lines(json.as_str(), &schema), Ok((passports.clone(), vec![])));
+        let csv = passports.to_csv(Some(&schema));
+        assert!(csv.starts_with("byr,iyr,eyr,hgt,hcl,ecl,pid,cid,valid,reasons\n1937,2017,2020,183cm,#fffffd,gry,860033327,147,true,\n"));
+        assert!(csv.ends_with(",false,missing hgt\n"));
+        assert_eq!(Passports::from_csv(csv.as_str(), &schema), Ok((passports, vec![])));
+    }
+
+    #[test]
+    fn import_anomalies() {
+        let schema = Schema::default();
+        let json = r#"{"pid":"1","pid":"2","zip":"3","hgt":"1 cm","ecl":""}"#;
+        let (passports, anomalies) = Passports::from_json_lines(json, &schema).unwrap();
+        assert_eq!(anomalies.iter().map(PassportAnomaly::to_string).collect::<Vec<String>>(), vec![
+            "record 1, line 1: duplicate key pid (1 and 2)",
+            "record 1, line 1: unknown key zip",
+            "record 1, line 1: malformed token \"hgt:1 cm\", expected key:value",
+            "record 1, line 1: malformed token \"ecl:\", expected key:value",
+        ]);
+        //the same rules as the records, so the last pid wins and what is kept can be written back
+        assert_eq!(passports.to_string(), "pid:2 zip:3");
+        let (_, anomalies) = Passports::from_csv("byr,a:b\n1937,x", &schema).unwrap();
+        assert_eq!(anomalies, vec![PassportAnomaly::MalformedToken { record: 1, line: 2, token: "a:b:x".to_string() }]);
+        assert_eq!(Passports::import("pid:1 pid:2", BatchFormat::Records, &schema).unwrap().1.len(), 1);
+    }
+
+    #[test]
+    fn import_errors() {
+        let schema = Schema::default();
+        assert_eq!(Passports::from_json_lines("{}\n[1]", &schema).unwrap_err().to_string(),
+            "Unable to import passports at line 2: expected an object");
+        assert_eq!(Passports::from_json_lines("{\"byr\":1937}", &schema).unwrap_err().to_string(),
+            "Unable to import passports at line 1: byr should be a string or null");
+        assert_eq!(Passports::from_json_lines("{\"byr\"", &schema).unwrap_err().to_string(),
+            "Unable to import passports at line 1: Unable to parse JSON at byte 6: expected ':'");
+        assert_eq!(Passports::from_csv("byr,iyr\n1937", &schema).unwrap_err().to_string(),
+            "Unable to import passports at line 2: expected 2 fields, found 1");
+    }
+}
@@ -0,0 +1,222 @@
+use super::{Passport, Passports, KNOWN_KEYS};
+use std::collections::BTreeSet;
+use std::fmt;
+
+const YEAR_KEYS : [&str; 3] = ["byr", "iyr", "eyr"];
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Difference {
+    Changed { key : String, first : Option<String>, second : Option<String> },
+    //the same three years, in different fields
+    SwappedYears,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difference::Changed { key, first, second } =>
+                write!(f, "{} {}/{}", key, first.as_deref().unwrap_or("-"), second.as_deref().unwrap_or("-")),
+            Difference::SwappedYears => write!(f, "years swapped"),
+        }
+    }
+}
+
+//two passports that are likely the same, `first` and `second` are positions in the batch
+#[derive(Debug, PartialEq, Clone)]
+pub struct DuplicatePair {
+    pub first : usize,
+    pub second : usize,
+    pub similarity : f64,
+    pub same_pid : bool,
+    pub differences : Vec<Difference>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DuplicateCluster {
+    pub passports : Vec<usize>,
+    pub pairs : Vec<DuplicatePair>,
+}
+
+impl DuplicateCluster {
+    //as similar as its least similar pair
+    pub fn similarity(&self) -> f64 {
+        self.pairs.iter().map(|pair| pair.similarity).fold(1.0, f64::min)
+    }
+}
+
+impl fmt::Display for DuplicateCluster {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        let members : Vec<String> = self.passports.iter().map(|i| (i + 1).to_string()).collect();
+        write!(f, "passports {}, similarity {:.2}", members.join(", "), self.similarity())?;
+        for pair in &self.pairs {
+            let differences : Vec<String> = pair.differences.iter().map(Difference::to_string).collect();
+            write!(f, "\n  {} ~ {}  {:.2}{}  {}", pair.first + 1, pair.second + 1, pair.similarity,
+                if pair.same_pid { " same pid" } else { "" },
+                if differences.is_empty() { "identical".to_string() } else { differences.join(", ") })?;
+        }
+        Ok(())
+    }
+}
+
+pub fn edit_distance(a : &str, b : &str) -> usize {
+    let b : Vec<char> = b.chars().collect();
+    let mut previous : Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn value_similarity(a : &str, b : &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(a, b) as f64 / longest as f64
+}
+
+fn years(passport : &Passport) -> Option<Vec<&str>> {
+    YEAR_KEYS.iter().map(|key| passport.get(key)).collect()
+}
+
+fn swapped_years(a : &Passport, b : &Passport) -> bool {
+    match (years(a), years(b)) {
+        (Some(mut a), Some(mut b)) if a != b => {
+            a.sort_unstable();
+            b.sort_unstable();
+            a == b
+        },
+        _ => false,
+    }
+}
+
+//the average similarity of every field either passport has, a field only one of them has counts as nothing alike
+pub fn compare(a : &Passport, b : &Passport) -> (f64, Vec<Difference>) {
+    let keys : BTreeSet<&str> = a.extra.keys().chain(b.extra.keys()).map(String::as_str).collect();
+    let keys : Vec<&str> = KNOWN_KEYS.iter().copied()
+        .chain(keys)
+        .filter(|key| a.get(key).is_some() || b.get(key).is_some())
+        .collect();
+    let swapped = swapped_years(a, b);
+    let mut differences = vec![];
+    if swapped {
+        differences.push(Difference::SwappedYears);
+    }
+    let mut total = 0.0;
+    for key in &keys {
+        let (first, second) = (a.get(key), b.get(key));
+        if swapped && YEAR_KEYS.contains(key) {
+            total += 1.0;
+            continue;
+        }
+        total += match (first, second) {
+            (Some(first), Some(second)) => value_similarity(first, second),
+            _ => 0.0,
+        };
+        if first != second {
+            differences.push(Difference::Changed {
+                key: key.to_string(), first: first.map(str::to_string), second: second.map(str::to_string),
+            });
+        }
+    }
+    let similarity = if keys.is_empty() { 1.0 } else { total / keys.len() as f64 };
+    (similarity, differences)
+}
+
+fn root(parents : &mut [usize], i : usize) -> usize {
+    let mut i = i;
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+impl Passports {
+    //pairs that share a pid or are at least `threshold` alike, grouped into clusters of passports linked by such pairs
+    pub fn duplicates(&self, threshold : f64) -> Vec<DuplicateCluster> {
+        let mut pairs = vec![];
+        for (i, a) in self.passports.iter().enumerate() {
+            for (j, b) in self.passports.iter().enumerate().skip(i + 1) {
+                let same_pid = a.passport_id.is_some() && a.passport_id == b.passport_id;
+                let (similarity, differences) = compare(a, b);
+                if same_pid || similarity >= threshold {
+                    pairs.push(DuplicatePair { first: i, second: j, similarity, same_pid, differences });
+                }
+            }
+        }
+
+        let mut parents : Vec<usize> = (0..self.passports.len()).collect();
+        for pair in &pairs {
+            let (a, b) = (root(&mut parents, pair.first), root(&mut parents, pair.second));
+            parents[b.max(a)] = b.min(a);
+        }
+        let mut clusters : Vec<DuplicateCluster> = vec![];
+        for pair in pairs {
+            let cluster = root(&mut parents, pair.first);
+            match clusters.iter_mut().find(|c| c.passports[0] == cluster) {
+                Some(c) => c.pairs.push(pair),
+                None => clusters.push(DuplicateCluster { passports: vec![cluster], pairs: vec![pair] }),
+            }
+        }
+        for cluster in &mut clusters {
+            let members : BTreeSet<usize> = cluster.pairs.iter().flat_map(|pair| vec![pair.first, pair.second]).collect();
+            cluster.passports = members.into_iter().collect();
+        }
+        clusters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const SAMPLE : &str = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884 hcl:#cfa07d byr:1929
+
+ecl:gry pid:860033327 eyr:2020 hcl:#fffffe byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:1929 ecl:amb cid:350 eyr:2013 pid:028048884 hcl:#cfa07d byr:2023
+
+ecl:brn pid:760753108 byr:1931 hgt:179cm
+
+ecl:brn pid:760753108 byr:1931 hgt:179cm hcl:#ae17e1 iyr:2013 eyr:2024
+
+ecl:gry pid:860033327 eyr:2020 byr:1937";
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("#fffffd", "#fffffd"), 0);
+    }
+
+    #[test]
+    fn find_duplicate_clusters() {
+        let passports = Passports::from_str(SAMPLE).unwrap();
+        let clusters = passports.duplicates(0.8);
+        assert_eq!(clusters.len(), 3);
+        assert_eq!(clusters[0].to_string(), "passports 1, 3, 7, similarity 0.50
+  1 ~ 3  0.98 same pid  hcl #fffffd/#fffffe
+  1 ~ 7  0.50 same pid  iyr 2017/-, hgt 183cm/-, hcl #fffffd/-, cid 147/-
+  3 ~ 7  0.50 same pid  iyr 2017/-, hgt 183cm/-, hcl #fffffe/-, cid 147/-");
+        assert_eq!(clusters[1].to_string(), "passports 2, 4, similarity 1.00
+  2 ~ 4  1.00 same pid  years swapped");
+        assert_eq!(clusters[2].passports, vec![4, 5]);
+        assert!(clusters[2].pairs[0].same_pid);
+        //without the shared pids only the typo and the swapped years are close enough
+        let mut different_ids = passports.clone();
+        for (i, passport) in different_ids.passports.iter_mut().enumerate() {
+            passport.passport_id = Some(i.to_string());
+        }
+        let clusters = different_ids.duplicates(0.85);
+        assert_eq!(clusters.iter().map(|c| c.passports.clone()).collect::<Vec<Vec<usize>>>(), vec![vec![0, 2], vec![1, 3]]);
+    }
+}
@@ -0,0 +1,136 @@
+use super::Passports;
+use super::schema::Schema;
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+//named variations on the puzzle's rules
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Profile {
+    //every field, cid included, has to be there and valid
+    PassportStrict,
+    //cid may be missing, the puzzle's part 2
+    NorthPole,
+    //fields only have to be there, the puzzle's part 1
+    Legacy,
+}
+
+pub const PROFILES : [Profile; 3] = [Profile::PassportStrict, Profile::NorthPole, Profile::Legacy];
+
+#[derive(Debug)]
+pub struct ProfileParseError;
+
+impl Error for ProfileParseError {}
+impl fmt::Display for ProfileParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse profile, expected one of passport-strict, north-pole or legacy")
+    }
+}
+
+impl FromStr for Profile {
+    type Err = ProfileParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        PROFILES.iter()
+            .find(|profile| profile.name() == s)
+            .copied()
+            .ok_or(ProfileParseError)
+    }
+}
+
+impl Profile {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Profile::PassportStrict => "passport-strict",
+            Profile::NorthPole => "north-pole",
+            Profile::Legacy => "legacy",
+        }
+    }
+
+    #[cfg(test)]
+    pub fn schema(&self) -> Schema {
+        self.apply(Schema::default())
+    }
+
+    //the profile's variation on any schema, such as one read with `--schema`
+    pub fn apply(&self, schema : Schema) -> Schema {
+        match self {
+            Profile::PassportStrict => schema.requiring("cid"),
+            Profile::NorthPole => schema,
+            Profile::Legacy => schema.presence_only(),
+        }
+    }
+}
+
+//how many passports each profile accepts
+pub struct ProfileCounts(pub Vec<(Profile, usize, usize)>);
+
+impl Passports {
+    pub fn profile_counts(&self, schema : &Schema) -> ProfileCounts {
+        ProfileCounts(PROFILES.iter()
+            .map(|profile| {
+                let valid = self.valid_passports_part2(&profile.apply(schema.clone()));
+                (*profile, valid, self.passports.len() - valid)
+            })
+            .collect())
+    }
+}
+
+impl fmt::Display for ProfileCounts {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:<16} {:>6} {:>9}", "profile", "valid", "rejected")?;
+        for (profile, valid, rejected) in &self.0 {
+            write!(f, "\n{:<16} {:>6} {:>9}", profile.name(), valid, rejected)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE : &str = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753108 byr:1931
+hgt:179cm
+
+hcl:#ae17e1 iyr:2013 eyr:2024 ecl:brn pid:760753108 byr:1931 hgt:179in
+
+hcl:#cfa07d eyr:2025 pid:166559648
+iyr:2011 ecl:brn hgt:59in";
+
+    #[test]
+    fn profiles_side_by_side() {
+        let passports = Passports::from_str(SAMPLE).unwrap();
+        assert_eq!(passports.profile_counts(&Schema::default()).to_string(), "profile           valid  rejected
+passport-strict       1         3
+north-pole            2         2
+legacy                3         1");
+        //legacy is the puzzle's part 1, north-pole its part 2
+        assert_eq!(passports.valid_passports_part1(&Schema::default()), 3);
+        assert_eq!(passports.valid_passports_part2(&Schema::default()), 2);
+    }
+
+    #[test]
+    fn parse_profiles() {
+        for profile in PROFILES.iter() {
+            assert_eq!(Profile::from_str(profile.name()).unwrap(), *profile);
+        }
+        assert!(Profile::from_str("strict").is_err());
+        assert_eq!(Profile::PassportStrict.schema().to_string().lines().last(), Some("cid required any"));
+        assert_eq!(Profile::Legacy.schema().to_string().lines().next(), Some("byr required any"));
+    }
+
+    #[test]
+    fn profiles_apply_to_other_schemas() {
+        let schema = Schema::from_str("byr required range 1900..=2000\ncid optional digits 3").unwrap();
+        assert_eq!(Profile::PassportStrict.apply(schema.clone()).to_string(), "byr required range 1900..=2000\ncid required digits 3");
+        assert_eq!(Profile::Legacy.apply(schema.clone()).to_string(), "byr required any\ncid optional any");
+        assert_eq!(Profile::NorthPole.apply(schema.clone()), schema);
+        let passports = Passports::from_str("byr:1950\n\nbyr:2010 cid:1").unwrap();
+        assert_eq!(passports.profile_counts(&schema).0.iter().map(|(_, valid, _)| *valid).collect::<Vec<usize>>(), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,448 @@
+use super::{Passport, Passports, KNOWN_KEYS};
+use super::schema::Schema;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+//filters such as `byr>=1950 and ecl in (blu,grn) and not valid`
+#[derive(Debug, PartialEq, Clone)]
+pub enum Query {
+    Compare { key : String, comparison : Comparison, value : String },
+    In { key : String, values : Vec<String> },
+    Has(String),
+    //passes the schema, so `valid` can't be used as a field name
+    Valid,
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct QueryParseError {
+    message : String,
+}
+
+impl Error for QueryParseError {}
+impl fmt::Display for QueryParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse query: {}", self.message)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Word(String),
+    Operator(String),
+    Open,
+    Close,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Word(word) | Token::Operator(word) => write!(f, "{}", word),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn is_operator(c : char) -> bool {
+    "=!<>".contains(c)
+}
+
+fn tokenize(s : &str) -> Vec<Token> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            },
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ',' => Token::Comma,
+            c if is_operator(c) => {
+                let mut operator = String::new();
+                while let Some(&c) = chars.peek().filter(|c| is_operator(**c)) {
+                    operator.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Operator(operator));
+                continue;
+            },
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek().filter(|c| !c.is_whitespace() && !is_operator(**c) && !"(),".contains(**c)) {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+                continue;
+            },
+        };
+        chars.next();
+        tokens.push(token);
+    }
+    tokens
+}
+
+struct Parser {
+    tokens : Vec<Token>,
+    position : usize,
+}
+
+impl Parser {
+    fn error<T>(&self, expected : &str) -> Result<T, QueryParseError> {
+        let found = match self.tokens.get(self.position) {
+            Some(token) => format!("{:?}", token.to_string()),
+            None => "the end".to_string(),
+        };
+        Err(QueryParseError { message: format!("expected {}, found {}", expected, found) })
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn accept(&mut self, token : &Token) -> bool {
+        if self.tokens.get(self.position) == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn keyword(&mut self, keyword : &str) -> bool {
+        self.accept(&Token::Word(keyword.to_string()))
+    }
+
+    fn word(&mut self, expected : &str) -> Result<String, QueryParseError> {
+        match self.tokens.get(self.position) {
+            Some(Token::Word(word)) => {
+                self.position += 1;
+                Ok(word.clone())
+            },
+            _ => self.error(expected),
+        }
+    }
+
+    fn or(&mut self) -> Result<Query, QueryParseError> {
+        let mut query = self.and()?;
+        while self.keyword("or") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryParseError> {
+        let mut query = self.unary()?;
+        while self.keyword("and") {
+            query = Query::And(Box::new(query), Box::new(self.unary()?));
+        }
+        Ok(query)
+    }
+
+    fn unary(&mut self) -> Result<Query, QueryParseError> {
+        if self.keyword("not") {
+            return Ok(Query::Not(Box::new(self.unary()?)));
+        }
+        if self.accept(&Token::Open) {
+            let query = self.or()?;
+            if !self.accept(&Token::Close) {
+                return self.error("')'");
+            }
+            return Ok(query);
+        }
+        if self.keyword("valid") {
+            return Ok(Query::Valid);
+        }
+        if self.keyword("has") {
+            return Ok(Query::Has(self.word("a field")?));
+        }
+        let key = self.word("a field")?;
+        if self.keyword("in") {
+            if !self.accept(&Token::Open) {
+                return self.error("'(' after in");
+            }
+            let mut values = vec![self.word("a value")?];
+            while self.accept(&Token::Comma) {
+                values.push(self.word("a value")?);
+            }
+            if !self.accept(&Token::Close) {
+                return self.error("',' or ')'");
+            }
+            return Ok(Query::In { key, values });
+        }
+        let comparison = match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) => match operator.as_str() {
+                "=" | "==" => Comparison::Equal,
+                "!=" => Comparison::NotEqual,
+                "<" => Comparison::Less,
+                "<=" => Comparison::LessOrEqual,
+                ">" => Comparison::Greater,
+                ">=" => Comparison::GreaterOrEqual,
+                _ => return self.error("one of =, !=, <, <=, >, >="),
+            },
+            _ => return self.error(&format!("a comparison or in after {}", key)),
+        };
+        self.next();
+        let value = self.word("a value")?;
+        Ok(Query::Compare { key, comparison, value })
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser { tokens: tokenize(s), position: 0 };
+        let query = parser.or()?;
+        if parser.position < parser.tokens.len() {
+            return parser.error("and, or or the end");
+        }
+        Ok(query)
+    }
+}
+
+fn split_number(s : &str) -> Option<(i64, &str)> {
+    let end = s.find(|c : char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s[..end].parse::<i64>().ok().map(|number| (number, &s[end..]))
+}
+
+//numbers with the same unit, or none, compare as numbers and text as text, anything else doesn't compare
+fn compare_values(a : &str, b : &str) -> Option<Ordering> {
+    match (split_number(a), split_number(b)) {
+        (Some((a, a_unit)), Some((b, b_unit))) if a_unit == b_unit => Some(a.cmp(&b)),
+        (None, None) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+//for sorting, numbers by unit and then value, then text
+fn order_values(a : &str, b : &str) -> Ordering {
+    match (split_number(a), split_number(b)) {
+        (Some((a, a_unit)), Some((b, b_unit))) => a_unit.cmp(b_unit).then(a.cmp(&b)),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => a.cmp(b),
+    }
+}
+
+impl Comparison {
+    fn matches(&self, ordering : Option<Ordering>) -> bool {
+        let ordering = match ordering {
+            Some(ordering) => ordering,
+            None => return *self == Comparison::NotEqual,
+        };
+        match self {
+            Comparison::Equal => ordering == Ordering::Equal,
+            Comparison::NotEqual => ordering != Ordering::Equal,
+            Comparison::Less => ordering == Ordering::Less,
+            Comparison::LessOrEqual => ordering != Ordering::Greater,
+            Comparison::Greater => ordering == Ordering::Greater,
+            Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+}
+
+const VALID_KEY : &str = "valid";
+const MISSING : &str = "(missing)";
+
+//passports indexed by every field they have, queries work on the positions in the batch
+pub struct PassportStore<'a> {
+    passports : &'a [Passport],
+    //field, then value, then the passports that have it
+    indexes : HashMap<String, BTreeMap<String, Vec<usize>>>,
+    valid : BTreeSet<usize>,
+}
+
+impl<'a> PassportStore<'a> {
+    pub fn new(passports : &'a Passports, schema : &Schema) -> PassportStore<'a> {
+        let mut indexes : HashMap<String, BTreeMap<String, Vec<usize>>> = HashMap::new();
+        for (i, passport) in passports.passports.iter().enumerate() {
+            let keys = KNOWN_KEYS.iter().copied().chain(passport.extra.keys().map(String::as_str));
+            for key in keys {
+                if let Some(value) = passport.get(key) {
+                    indexes.entry(key.to_string()).or_default().entry(value.to_string()).or_default().push(i);
+                }
+            }
+        }
+        let valid = passports.passports.iter()
+            .enumerate()
+            .filter(|(_, passport)| schema.is_valid(passport))
+            .map(|(i, _)| i)
+            .collect();
+        PassportStore { passports: &passports.passports, indexes, valid }
+    }
+
+    pub fn passport(&self, i : usize) -> &'a Passport {
+        &self.passports[i]
+    }
+
+    pub fn all(&self) -> BTreeSet<usize> {
+        (0..self.passports.len()).collect()
+    }
+
+    fn lookup(&self, key : &str, value : &str) -> &[usize] {
+        self.indexes.get(key)
+            .and_then(|index| index.get(value))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    #[cfg(test)]
+    pub fn find_by_id(&self, id : &str) -> Option<&'a Passport> {
+        self.lookup("pid", id).first().map(|i| self.passport(*i))
+    }
+
+    //equality goes straight to the index, other comparisons look at each distinct value once
+    pub fn select(&self, query : &Query) -> BTreeSet<usize> {
+        match query {
+            Query::Compare { key, comparison: Comparison::Equal, value } => self.lookup(key, value).iter().copied().collect(),
+            Query::Compare { key, comparison, value } => self.indexes.get(key).into_iter()
+                .flatten()
+                .filter(|(found, _)| comparison.matches(compare_values(found, value)))
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect(),
+            Query::In { key, values } => values.iter()
+                .flat_map(|value| self.lookup(key, value).iter().copied())
+                .collect(),
+            Query::Has(key) => self.indexes.get(key).into_iter()
+                .flat_map(|index| index.values().flatten().copied())
+                .collect(),
+            Query::Valid => self.valid.clone(),
+            Query::Not(query) => self.all().difference(&self.select(query)).copied().collect(),
+            Query::And(a, b) => self.select(a).intersection(&self.select(b)).copied().collect(),
+            Query::Or(a, b) => self.select(a).union(&self.select(b)).copied().collect(),
+        }
+    }
+
+    //the most common values first, `valid` groups by whether passports pass the schema
+    pub fn group_by(&self, key : &str, ids : &BTreeSet<usize>) -> Vec<(String, usize)> {
+        let mut groups : Vec<(String, usize)> = if key == VALID_KEY {
+            let valid = ids.intersection(&self.valid).count();
+            vec![("true".to_string(), valid), ("false".to_string(), ids.len() - valid)]
+        } else {
+            self.indexes.get(key).into_iter()
+                .flatten()
+                .map(|(value, matches)| (value.clone(), matches.iter().filter(|i| ids.contains(i)).count()))
+                .collect()
+        };
+        let grouped : usize = groups.iter().map(|(_, count)| count).sum();
+        groups.push((MISSING.to_string(), ids.len() - grouped));
+        groups.retain(|(_, count)| *count > 0);
+        groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| order_values(&a.0, &b.0)));
+        groups
+    }
+
+    //passports without the field go last, ties keep the batch order
+    pub fn sorted(&self, ids : &BTreeSet<usize>, key : &str) -> Vec<usize> {
+        let mut sorted : Vec<usize> = ids.iter().copied().collect();
+        sorted.sort_by(|a, b| match (self.passport(*a).get(key), self.passport(*b).get(key)) {
+            (Some(a), Some(b)) => order_values(a, b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        sorted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE : &str = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+hcl:#ae17e1 iyr:2013
+eyr:2024
+ecl:brn pid:760753108 byr:1931
+hgt:179cm
+
+hcl:#cfa07d eyr:2025 pid:166559648
+iyr:2011 ecl:brn hgt:59in
+
+pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f";
+
+    fn select(query : &str) -> Vec<usize> {
+        let passports = Passports::from_str(SAMPLE).unwrap();
+        let store = PassportStore::new(&passports, &Schema::default());
+        store.select(&Query::from_str(query).unwrap()).into_iter().collect()
+    }
+
+    #[test]
+    fn parse_queries() {
+        assert_eq!(Query::from_str("byr>=1950 and not valid").unwrap(), Query::And(
+            Box::new(Query::Compare { key: "byr".to_string(), comparison: Comparison::GreaterOrEqual, value: "1950".to_string() }),
+            Box::new(Query::Not(Box::new(Query::Valid))),
+        ));
+        assert_eq!(Query::from_str("ecl in (blu, grn)").unwrap(), Query::In {
+            key: "ecl".to_string(), values: vec!["blu".to_string(), "grn".to_string()],
+        });
+    }
+
+    #[test]
+    fn query_errors() {
+        let error = |query : &str| Query::from_str(query).unwrap_err().to_string();
+        assert_eq!(error("byr"), "Unable to parse query: expected a comparison or in after byr, found the end");
+        assert_eq!(error("byr => 1950"), "Unable to parse query: expected one of =, !=, <, <=, >, >=, found \"=>\"");
+        assert_eq!(error("ecl in (blu grn)"), "Unable to parse query: expected ',' or ')', found \"grn\"");
+        assert_eq!(error("(valid"), "Unable to parse query: expected ')', found the end");
+        assert_eq!(error("valid valid"), "Unable to parse query: expected and, or or the end, found \"valid\"");
+        assert_eq!(error("and"), "Unable to parse query: expected a comparison or in after and, found the end");
+    }
+
+    #[test]
+    fn select_passports() {
+        assert_eq!(select("byr>=1930"), vec![0, 2, 4]);
+        assert_eq!(select("byr>=1930 and ecl in (brn,grn) and valid"), vec![2, 4]);
+        assert_eq!(select("not valid"), vec![1, 3]);
+        assert_eq!(select("not has byr or hgt<60in"), vec![3]);
+        assert_eq!(select("hgt>180cm or pid=028048884"), vec![0, 1]);
+        //missing fields never match a comparison
+        assert_eq!(select("hcl=#cfa07d and not (cid!=350)"), vec![1, 3]);
+        assert_eq!(select("hgt!=60in and hgt<=183cm"), vec![0, 2]);
+        assert_eq!(select("zip=1"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn group_and_sort() {
+        let passports = Passports::from_str(SAMPLE).unwrap();
+        let store = PassportStore::new(&passports, &Schema::default());
+        let all = store.all();
+        assert_eq!(store.group_by("ecl", &all), vec![
+            ("brn".to_string(), 2), ("amb".to_string(), 1), ("grn".to_string(), 1), ("gry".to_string(), 1),
+        ]);
+        assert_eq!(store.group_by("cid", &all), vec![
+            (MISSING.to_string(), 3), ("147".to_string(), 1), ("350".to_string(), 1),
+        ]);
+        assert_eq!(store.group_by("valid", &store.select(&Query::from_str("ecl=brn").unwrap())), vec![
+            ("false".to_string(), 1), ("true".to_string(), 1),
+        ]);
+        assert_eq!(store.sorted(&all, "byr"), vec![1, 2, 0, 4, 3]);
+        assert_eq!(store.sorted(&all, "hgt"), vec![2, 0, 3, 4, 1]);
+        assert_eq!(store.find_by_id("760753108"), Some(&passports.passports[2]));
+    }
+}
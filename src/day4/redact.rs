@@ -0,0 +1,312 @@
+use super::{Passport, Passports};
+use super::schema::{FieldType, Schema};
+use crate::siphash::SipKey;
+use std::{error::Error, fmt};
+
+const IDENTIFIER_KEYS : [&str; 2] = ["pid", "cid"];
+const YEAR_KEYS : [&str; 3] = ["byr", "iyr", "eyr"];
+//steps along the keyed order per value before giving up
+const ATTEMPTS : u64 = 1000;
+const ROUNDS : u32 = 8;
+
+//the passphrase is read from here or from a file, never from the command line where `ps` and the shell history would keep it
+pub const PASSPHRASE_VAR : &str = "DAY4_REDACT_PASSPHRASE";
+
+#[derive(Debug, PartialEq)]
+pub enum RedactionError {
+    MissingPassphrase,
+    PassphraseOnCommandLine,
+    NoPseudonym { key : String, value : String },
+}
+
+impl Error for RedactionError {}
+impl fmt::Display for RedactionError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RedactionError::MissingPassphrase =>
+                write!(f, "Unable to redact: set {} or give --passphrase-file", PASSPHRASE_VAR),
+            RedactionError::PassphraseOnCommandLine =>
+                write!(f, "Unable to redact: the passphrase belongs in {} or a --passphrase-file, not on the command line", PASSPHRASE_VAR),
+            RedactionError::NoPseudonym { key, value } =>
+                write!(f, "Unable to redact {}={:?}: no pseudonym passes the same rules", key, value),
+        }
+    }
+}
+
+//how to hide identifiers, the same key always gives the same pseudonyms
+pub struct Redaction {
+    pub key : SipKey,
+    //round years down to this many years, without moving them in or out of the schema's range
+    pub year_bucket : Option<i64>,
+}
+
+//(radix, first character) of a character a pseudonym changes, anything else is kept
+fn radix(c : char) -> Option<(u128, u8)> {
+    match c {
+        '0'..='9' => Some((10, b'0')),
+        'a'..='z' => Some((26, b'a')),
+        'A'..='Z' => Some((26, b'A')),
+        _ => None,
+    }
+}
+
+//a keyed permutation of the numbers below 2^(2 * half), or its inverse
+fn feistel(key : &SipKey, tweak : &str, half : u32, x : u128, inverse : bool) -> u128 {
+    let mask = (1u128 << half) - 1;
+    let f = |round : u32, v : u128| key.hash(format!("{}:{}:{}", tweak, round, v).as_bytes()) as u128 & mask;
+    let (mut left, mut right) = (x >> half, x & mask);
+    if inverse {
+        for round in (0..ROUNDS).rev() {
+            let previous = right ^ f(round, left);
+            right = left;
+            left = previous;
+        }
+    } else {
+        for round in 0..ROUNDS {
+            let next = left ^ f(round, right);
+            left = right;
+            right = next;
+        }
+    }
+    left << half | right
+}
+
+//the same permutation on the numbers below size, walked until it lands there again
+fn feistel_below(key : &SipKey, tweak : &str, half : u32, size : u128, mut x : u128, inverse : bool) -> u128 {
+    loop {
+        x = feistel(key, tweak, half, x, inverse);
+        if x < size {
+            return x;
+        }
+    }
+}
+
+//every digit becomes another digit and every letter another letter of the same case, anything else is kept,
+//so a pseudonym has the same shape as the value it replaces, it is the value after this one when all the values
+//of that shape are put in a keyed order, so no two values share one and none keeps its own,
+//None when there are too many digits and letters to number them
+fn pseudonym(key : &SipKey, field : &str, value : &str) -> Option<String> {
+    let mut size : u128 = 1;
+    let mut number : u128 = 0;
+    for c in value.chars() {
+        if let Some((radix, first)) = radix(c) {
+            size = size.checked_mul(radix)?;
+            number = number * radix + (c as u8 - first) as u128;
+        }
+    }
+    let half = (128 - (size - 1).leading_zeros()).div_ceil(2).max(1);
+    if half > 63 {
+        return None;
+    }
+    let shape : String = value.chars().map(|c| radix(c).map_or(c, |(_, first)| first as char)).collect();
+    let tweak = format!("{}:{}", field, shape);
+    let position = feistel_below(key, &tweak, half, size, number, false);
+    number = feistel_below(key, &tweak, half, size, (position + 1) % size, true);
+    let mut out : Vec<char> = value.chars().rev()
+        .map(|c| match radix(c) {
+            Some((radix, first)) => {
+                let digit = (number % radix) as u8;
+                number /= radix;
+                (first + digit) as char
+            },
+            None => c,
+        })
+        .collect();
+    out.reverse();
+    Some(out.into_iter().collect())
+}
+
+//the first value along the keyed order that the field's rule treats like the value, the shape alone isn't
+//enough for rules such as ranges, skipping the others still gives every value its own pseudonym
+fn choose(key : &SipKey, field : &str, value : &str, kind : Option<&FieldType>) -> Result<String, RedactionError> {
+    let valid = |v : &str| kind.is_none_or(|kind| kind.violation(v).is_none());
+    let error = || RedactionError::NoPseudonym { key: field.to_string(), value: value.to_string() };
+    //nothing in it to hide
+    if !value.chars().any(|c| radix(c).is_some()) {
+        return Ok(value.to_string());
+    }
+    let mut current = value.to_string();
+    for _ in 0..ATTEMPTS {
+        current = pseudonym(key, field, &current).ok_or_else(error)?;
+        //all the way round, so no other value passes the same rules
+        if current == value {
+            break;
+        }
+        if valid(&current) == valid(value) {
+            return Ok(current);
+        }
+    }
+    Err(error())
+}
+
+fn bucket(year : &str, width : i64, kind : Option<&FieldType>) -> Option<String> {
+    if width < 1 {
+        return None;
+    }
+    let year = year.parse::<i64>().ok().filter(|_| year.chars().all(|c| c.is_ascii_digit()))?;
+    let start = year - year.rem_euclid(width);
+    let bucketed = match kind {
+        //a valid year stays at or above the minimum, an invalid one above the maximum stays above it
+        Some(FieldType::Range { min, max }) if year >= *min && year <= *max => start.max(*min),
+        Some(FieldType::Range { max, .. }) if year > *max => start.max(max + 1),
+        _ => start,
+    };
+    Some(bucketed.to_string())
+}
+
+impl Passports {
+    //a value gets the same pseudonym wherever it appears, whatever else is in the batch
+    pub fn redacted(&self, redaction : &Redaction, schema : &Schema) -> Result<Passports, RedactionError> {
+        let mut passports = vec![];
+        for passport in &self.passports {
+            let mut redacted : Passport = passport.clone();
+            for key in IDENTIFIER_KEYS.iter() {
+                let value = match passport.get(key) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                let kind = schema.field(key).map(|field| &field.kind);
+                let replacement = choose(&redaction.key, key, value, kind)?;
+                redacted.set(key, &replacement);
+            }
+            if let Some(width) = redaction.year_bucket {
+                for key in YEAR_KEYS.iter() {
+                    let kind = schema.field(key).map(|field| &field.kind);
+                    if let Some(year) = passport.get(key).and_then(|year| bucket(year, width, kind)) {
+                        redacted.set(key, &year);
+                    }
+                }
+            }
+            passports.push(redacted);
+        }
+        Ok(Passports { passports })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::profile::PROFILES;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    const SAMPLE : &str = "ecl:gry pid:060033327 eyr:2020 hcl:#fffffd
+byr:1937 iyr:2017 cid:147 hgt:183cm
+
+iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
+hcl:#cfa07d byr:1929
+
+eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+hgt:59cm ecl:zzz
+eyr:2038 hcl:74454a iyr:2023
+pid:3556412378 byr:2007
+
+pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+hcl:#623a2f";
+
+    fn redaction(passphrase : &str, year_bucket : Option<i64>) -> Redaction {
+        Redaction { key: SipKey::from_passphrase(passphrase), year_bucket }
+    }
+
+    #[test]
+    fn pseudonyms_keep_the_shape() {
+        let key = SipKey::from_passphrase("vendor");
+        let pid = pseudonym(&key, "pid", "060033327").unwrap();
+        assert_eq!(pid.len(), 9);
+        assert!(pid.chars().all(|c| c.is_ascii_digit()));
+        assert_ne!(pid, "060033327");
+        assert_eq!(pseudonym(&key, "pid", "060033327"), Some(pid.clone()));
+        assert_ne!(pseudonym(&key, "cid", "060033327"), Some(pid.clone()));
+        let odd = pseudonym(&key, "pid", "#cb-84E").unwrap();
+        assert!(odd.starts_with('#') && odd.chars().nth(3) == Some('-'));
+        assert!(odd[1..3].chars().all(|c| c.is_ascii_lowercase()) && odd[4..6].chars().all(|c| c.is_ascii_digit()));
+        assert!(odd[6..].chars().all(|c| c.is_ascii_uppercase()));
+        assert_ne!(pseudonym(&SipKey::from_passphrase("other"), "pid", "060033327"), Some(pid));
+        //27 digits and letters can't be numbered in a u128
+        assert_eq!(pseudonym(&key, "pid", &"z".repeat(27)), None);
+        assert!(pseudonym(&key, "pid", &"9".repeat(37)).is_some());
+    }
+
+    #[test]
+    fn year_buckets_keep_validity() {
+        let range = FieldType::Range { min: 1920, max: 2002 };
+        assert_eq!(bucket("1937", 10, Some(&range)), Some("1930".to_string()));
+        assert_eq!(bucket("1923", 10, Some(&range)), Some("1920".to_string()));
+        assert_eq!(bucket("1921", 5, Some(&FieldType::Range { min: 1922, max: 2002 })), Some("1920".to_string()));
+        assert_eq!(bucket("1925", 10, Some(&FieldType::Range { min: 1922, max: 2002 })), Some("1922".to_string()));
+        assert_eq!(bucket("2007", 10, Some(&range)), Some("2003".to_string()));
+        assert_eq!(bucket("2002", 10, Some(&range)), Some("2000".to_string()));
+        assert_eq!(bucket("+1937", 10, Some(&range)), None);
+        assert_eq!(bucket("1937", 10, None), Some("1930".to_string()));
+        assert_eq!(bucket("1937", 0, None), None);
+    }
+
+    #[test]
+    fn redaction_keeps_the_counts() {
+        let passports = Passports::from_str(SAMPLE).unwrap();
+        let schema = Schema::default();
+        for year_bucket in &[None, Some(5), Some(10), Some(50)] {
+            let redacted = passports.redacted(&redaction("vendor", *year_bucket), &schema).unwrap();
+            for profile in PROFILES.iter() {
+                let schema = profile.schema();
+                let valid = |passports : &Passports| passports.passports.iter().map(|p| schema.is_valid(p)).collect::<Vec<bool>>();
+                assert_eq!(valid(&redacted), valid(&passports));
+            }
+            assert_eq!(redacted.valid_passports_part1(&schema), passports.valid_passports_part1(&schema));
+            assert!(redacted.passports.iter().zip(&passports.passports).all(|(r, p)| r.passport_id != p.passport_id));
+        }
+        let redacted = passports.redacted(&redaction("vendor", None), &schema).unwrap();
+        assert_eq!(redacted, passports.redacted(&redaction("vendor", None), &schema).unwrap());
+        assert_eq!(redacted.passports[0].birth_year, passports.passports[0].birth_year);
+        assert_ne!(redacted, passports.redacted(&redaction("another vendor", None), &schema).unwrap());
+    }
+
+    #[test]
+    fn pseudonyms_follow_the_schema() {
+        let passports = Passports::from_str(SAMPLE).unwrap();
+        let schema = Schema::from_str(&format!("{}\ncid required range 100..=200", Schema::default().to_string().replace("\ncid optional any", ""))).unwrap();
+        let valid = |passports : &Passports| passports.passports.iter().map(|p| schema.is_valid(p)).collect::<Vec<bool>>();
+        for passphrase in &["vendor", "another vendor", "a third vendor", "x", "y", "z"] {
+            let redacted = passports.redacted(&redaction(passphrase, None), &schema).unwrap();
+            assert_eq!(valid(&redacted), valid(&passports), "{}", passphrase);
+            assert_eq!(redacted.valid_passports_part2(&schema), passports.valid_passports_part2(&schema));
+        }
+    }
+
+    #[test]
+    fn pseudonyms_do_not_depend_on_the_batch() {
+        let passports = Passports::from_str(SAMPLE).unwrap();
+        let schema = Schema::default();
+        let redacted = passports.redacted(&redaction("vendor", None), &schema).unwrap();
+        //the last passport alone, and the passports in reverse
+        let alone = Passports { passports: vec![passports.passports[4].clone()] }.redacted(&redaction("vendor", None), &schema).unwrap();
+        assert_eq!(alone.passports[0], redacted.passports[4]);
+        let reversed = Passports { passports: passports.passports.iter().rev().cloned().collect() };
+        let redacted_reversed = reversed.redacted(&redaction("vendor", None), &schema).unwrap();
+        assert_eq!(redacted_reversed.passports.iter().rev().cloned().collect::<Vec<Passport>>(), redacted.passports);
+    }
+
+    #[test]
+    fn pseudonyms_are_unique() {
+        let key = SipKey::from_passphrase("vendor");
+        let range = FieldType::Range { min: 100, max: 200 };
+        //every three digit cid, so any two that shared a pseudonym would show up
+        let values : Vec<String> = (0..1000).map(|i| format!("{:03}", i)).collect();
+        let pseudonyms : HashSet<String> = values.iter()
+            .map(|value| {
+                let pseudonym = choose(&key, "cid", value, Some(&range)).unwrap();
+                assert_eq!(range.violation(&pseudonym).is_none(), range.violation(value).is_none(), "{}", value);
+                assert_ne!(&pseudonym, value);
+                pseudonym
+            })
+            .collect();
+        assert_eq!(pseudonyms.len(), 1000);
+        assert_eq!(choose(&key, "cid", "#-", Some(&range)), Ok("#-".to_string()));
+        //only the word itself passes, so the walk comes back to it
+        let word = FieldType::OneOf(vec!["abc".to_string()]);
+        assert_eq!(choose(&key, "pid", "abc", Some(&word)).unwrap_err().to_string(),
+            "Unable to redact pid=\"abc\": no pseudonym passes the same rules");
+    }
+}
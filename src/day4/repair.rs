@@ -0,0 +1,211 @@
+use super::Passport;
+use super::duplicates::edit_distance;
+use super::report::ValidationReport;
+use super::schema::{FieldType, Schema};
+use std::cmp::Reverse;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Confidence {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Confidence::Low => write!(f, "low"),
+            Confidence::Medium => write!(f, "medium"),
+            Confidence::High => write!(f, "high"),
+        }
+    }
+}
+
+//a value that would pass the rule, never applied, only reported
+#[derive(Debug, PartialEq, Clone)]
+pub struct Repair {
+    pub key : String,
+    pub from : String,
+    pub to : String,
+    pub confidence : Confidence,
+    pub reason : &'static str,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} -> {} ({}, {})", self.key, self.from, self.to, self.confidence, self.reason)
+    }
+}
+
+//what is wrong with a passport and what might fix it
+#[derive(Debug, PartialEq, Clone)]
+pub struct RepairReport {
+    pub report : ValidationReport,
+    pub repairs : Vec<Repair>,
+}
+
+impl RepairReport {
+    //nothing missing, and a suggestion for every invalid field
+    pub fn is_fixable(&self) -> bool {
+        self.report.missing.is_empty()
+            && self.report.invalid.iter().all(|error| self.repairs.iter().any(|repair| repair.key == error.key))
+    }
+}
+
+impl fmt::Display for RepairReport {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.report)?;
+        for repair in &self.repairs {
+            write!(f, "\n  suggest {}", repair)?;
+        }
+        Ok(())
+    }
+}
+
+fn in_range(value : &str, min : i64, max : i64) -> bool {
+    value.parse::<i64>().is_ok_and(|i| i >= min && i <= max)
+}
+
+fn is_digits(value : &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_digit())
+}
+
+//likely fixes for a value that breaks the rule, the caller drops any that still don't pass
+fn candidates(kind : &FieldType, value : &str) -> Vec<(String, Confidence, &'static str)> {
+    let mut found = vec![];
+    match kind {
+        FieldType::Any => {},
+        FieldType::Range { min, max } => {
+            if value.len() == 2 && is_digits(value) {
+                for century in &["19", "20"] {
+                    let year = format!("{}{}", century, value);
+                    if in_range(&year, *min, *max) {
+                        found.push((year, Confidence::Low, "expand the two digit year"));
+                    }
+                }
+            }
+        },
+        FieldType::Units(units) => {
+            let lower = value.to_ascii_lowercase();
+            if lower != value {
+                found.push((lower, Confidence::High, "lowercase the unit"));
+            }
+            if is_digits(value) {
+                let fitting : Vec<&String> = units.iter()
+                    .filter(|(_, min, max)| in_range(value, *min, *max))
+                    .map(|(unit, _, _)| unit)
+                    .collect();
+                let confidence = if fitting.len() == 1 { Confidence::High } else { Confidence::Medium };
+                for unit in fitting {
+                    found.push((format!("{}{}", value, unit), confidence, "add the missing unit"));
+                }
+            }
+            if let Some((unit, _, _)) = units.iter().find(|(unit, _, _)| value.ends_with(unit.as_str())) {
+                let number = &value[..value.len() - unit.len()];
+                for (other, min, max) in units.iter().filter(|(other, _, _)| other != unit) {
+                    if in_range(number, *min, *max) {
+                        found.push((format!("{}{}", number, other), Confidence::Low, "the number fits another unit"));
+                    }
+                }
+            }
+        },
+        FieldType::Color => {
+            if !value.starts_with('#') {
+                found.push((format!("#{}", value), Confidence::High, "add the missing #"));
+            }
+            let lower = value.to_ascii_lowercase();
+            if lower != value {
+                found.push((lower, Confidence::High, "lowercase the hex digits"));
+            }
+        },
+        FieldType::OneOf(values) => {
+            if let Some(allowed) = values.iter().find(|allowed| allowed.eq_ignore_ascii_case(value)) {
+                found.push((allowed.clone(), Confidence::High, "fix the case"));
+            }
+            //a case fix is already suggested above
+            let close : Vec<&String> = values.iter()
+                .filter(|allowed| edit_distance(allowed, value) == 1 && !found.iter().any(|(to, _, _)| to == *allowed))
+                .collect();
+            let confidence = if close.len() == 1 { Confidence::Medium } else { Confidence::Low };
+            for allowed in close {
+                found.push((allowed.clone(), confidence, "the closest allowed value"));
+            }
+        },
+        FieldType::Digits(length) => {
+            if is_digits(value) && value.len() < *length {
+                found.push((format!("{:0>width$}", value, width = length), Confidence::Medium, "zero-pad"));
+            }
+            if is_digits(value) && value.len() > *length && value[..value.len() - length].chars().all(|c| c == '0') {
+                found.push((value[value.len() - length..].to_string(), Confidence::Medium, "drop the extra leading zeros"));
+            }
+        },
+    }
+    found
+}
+
+impl Schema {
+    //the most likely fixes first
+    pub fn suggest_repairs(&self, passport : &Passport) -> RepairReport {
+        let report = self.validate(passport);
+        let mut repairs = vec![];
+        for error in &report.invalid {
+            let kind = match self.field(&error.key) {
+                Some(field) => &field.kind,
+                None => continue,
+            };
+            let mut found : Vec<Repair> = candidates(kind, &error.value).into_iter()
+                .filter(|(to, _, _)| kind.violation(to).is_none())
+                .map(|(to, confidence, reason)| Repair { key: error.key.clone(), from: error.value.clone(), to, confidence, reason })
+                .collect();
+            found.sort_by_key(|repair| Reverse(repair.confidence));
+            repairs.extend(found);
+        }
+        RepairReport { report, repairs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn repairs(record : &str) -> Vec<String> {
+        let passport = Passport::from_str(record).unwrap();
+        Schema::default().suggest_repairs(&passport).repairs.iter().map(Repair::to_string).collect()
+    }
+
+    #[test]
+    fn suggest_field_repairs() {
+        assert_eq!(repairs("hgt:183"), vec!["hgt: 183 -> 183cm (high, add the missing unit)"]);
+        assert_eq!(repairs("hgt:60"), vec!["hgt: 60 -> 60in (high, add the missing unit)"]);
+        assert_eq!(repairs("hgt:183CM"), vec!["hgt: 183CM -> 183cm (high, lowercase the unit)"]);
+        assert_eq!(repairs("hgt:180in"), vec!["hgt: 180in -> 180cm (low, the number fits another unit)"]);
+        assert_eq!(repairs("pid:12345"), vec!["pid: 12345 -> 000012345 (medium, zero-pad)"]);
+        assert_eq!(repairs("pid:0123456789"), vec!["pid: 0123456789 -> 123456789 (medium, drop the extra leading zeros)"]);
+        assert_eq!(repairs("hcl:a97842"), vec!["hcl: a97842 -> #a97842 (high, add the missing #)"]);
+        assert_eq!(repairs("ecl:BRN"), vec!["ecl: BRN -> brn (high, fix the case)"]);
+        assert_eq!(repairs("ecl:Gry"), vec!["ecl: Gry -> gry (high, fix the case)"]);
+        assert_eq!(repairs("ecl:blw"), vec!["ecl: blw -> blu (medium, the closest allowed value)"]);
+        assert_eq!(repairs("ecl:gr"), vec!["ecl: gr -> gry (low, the closest allowed value)", "ecl: gr -> grn (low, the closest allowed value)"]);
+        assert_eq!(repairs("byr:87"), vec!["byr: 87 -> 1987 (low, expand the two digit year)"]);
+        assert_eq!(repairs("hcl:#ABCDEF"), vec!["hcl: #ABCDEF -> #abcdef (high, lowercase the hex digits)"]);
+        //nothing sensible to suggest
+        assert!(repairs("hgt:20cm hcl:z ecl:zzz pid:#cbc84e byr:2007").is_empty());
+    }
+
+    #[test]
+    fn repair_reports() {
+        let schema = Schema::default();
+        let passport = Passport::from_str("byr:1937 iyr:2017 eyr:2020 hgt:183 hcl:fffffd ecl:gry pid:60033327").unwrap();
+        let report = schema.suggest_repairs(&passport);
+        assert!(report.is_fixable());
+        assert_eq!(report.to_string(), "hgt=183: must end in cm or in; hcl=fffffd: must be # followed by six lowercase hex digits; pid=60033327: must be exactly 9 digits
+  suggest hgt: 183 -> 183cm (high, add the missing unit)
+  suggest hcl: fffffd -> #fffffd (high, add the missing #)
+  suggest pid: 60033327 -> 060033327 (medium, zero-pad)");
+        //suggestions are only reported, the passport is left as it was
+        assert!(!schema.is_valid(&passport));
+        assert!(!schema.suggest_repairs(&Passport::from_str("hgt:183").unwrap()).is_fixable());
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldError {
+    pub key : String,
+    pub value : String,
+    pub reason : String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}: {}", self.key, self.value, self.reason)
+    }
+}
+
+//why a single passport was rejected, if it was
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ValidationReport {
+    pub missing : Vec<String>,
+    pub invalid : Vec<FieldError>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.invalid.is_empty()
+    }
+
+    //one entry per problem, worded the same for every passport so they can be counted
+    pub fn reasons(&self) -> impl Iterator<Item = String> + '_ {
+        self.missing.iter()
+            .map(|key| format!("missing {}", key))
+            .chain(self.invalid.iter().map(|error| format!("{}: {}", error.key, error.reason)))
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "valid");
+        }
+        let mut problems = vec![];
+        if !self.missing.is_empty() {
+            problems.push(format!("missing {}", self.missing.join(", ")));
+        }
+        problems.extend(self.invalid.iter().map(FieldError::to_string));
+        write!(f, "{}", problems.join("; "))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ValidationSummary {
+    pub passports : usize,
+    pub valid : usize,
+    pub failures : BTreeMap<String, usize>,
+}
+
+impl ValidationSummary {
+    pub fn record(&mut self, report : &ValidationReport) {
+        self.passports += 1;
+        if report.is_valid() {
+            self.valid += 1;
+        }
+        for reason in report.reasons() {
+            *self.failures.entry(reason).or_insert(0) += 1;
+        }
+    }
+}
+
+impl fmt::Display for ValidationSummary {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} passports: {} valid, {} rejected", self.passports, self.valid, self.passports - self.valid)?;
+        //most common first
+        let mut failures : Vec<(&String, &usize)> = self.failures.iter().collect();
+        failures.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for (reason, count) in failures {
+            write!(f, "\n  {:>5}  {}", count, reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> ValidationReport {
+        ValidationReport {
+            missing: vec!["byr".to_string(), "pid".to_string()],
+            invalid: vec![FieldError { key: "hgt".to_string(), value: "190in".to_string(), reason: "must be 59..=76 in".to_string() }],
+        }
+    }
+
+    #[test]
+    fn explain_rejection() {
+        assert_eq!(report().to_string(), "missing byr, pid; hgt=190in: must be 59..=76 in");
+        assert_eq!(ValidationReport::default().to_string(), "valid");
+    }
+
+    #[test]
+    fn count_reasons() {
+        let mut summary = ValidationSummary::default();
+        summary.record(&report());
+        summary.record(&ValidationReport { missing: vec!["pid".to_string()], invalid: vec![] });
+        summary.record(&ValidationReport::default());
+        assert_eq!(summary.to_string(), "3 passports: 1 valid, 2 rejected
+      2  missing pid
+      1  hgt: must be 59..=76 in
+      1  missing byr");
+    }
+}
@@ -0,0 +1,294 @@
+use super::Passport;
+use super::report::{FieldError, ValidationReport};
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+//the puzzle's rules, in the schema format
+pub const DEFAULT_SCHEMA : &str = "# key required|optional type
+byr required range 1920..=2002
+iyr required range 2010..=2020
+eyr required range 2020..=2030
+hgt required units 150..=193cm 59..=76in
+hcl required color
+ecl required one-of amb blu brn gry grn hzl oth
+pid required digits 9
+# no cid is ok as that means its from the north pole
+cid optional any";
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum FieldType {
+    Any,
+    Range { min : i64, max : i64 },
+    //a number followed by one of the units, each unit with its own range
+    Units(Vec<(String, i64, i64)>),
+    //`#` and six lowercase hex digits
+    Color,
+    OneOf(Vec<String>),
+    Digits(usize),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FieldRule {
+    pub key : String,
+    pub required : bool,
+    pub kind : FieldType,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Schema {
+    fields : Vec<FieldRule>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SchemaParseError {
+    line : usize,
+    message : String,
+}
+
+impl Error for SchemaParseError {}
+impl fmt::Display for SchemaParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse schema at line {}: {}", self.line, self.message)
+    }
+}
+
+fn parse_range(s : &str) -> Option<(i64, i64)> {
+    let mut bounds = s.splitn(2, "..=");
+    let min = bounds.next()?.parse::<i64>().ok()?;
+    let max = bounds.next()?.parse::<i64>().ok()?;
+    Some((min, max))
+}
+
+impl FieldType {
+    fn parse(tokens : &[&str]) -> Result<FieldType, String> {
+        match tokens {
+            ["any"] => Ok(FieldType::Any),
+            ["range", range] => parse_range(range)
+                .map(|(min, max)| FieldType::Range { min, max })
+                .ok_or_else(|| format!("expected a range such as 1920..=2002, found {:?}", range)),
+            ["units", units @ ..] if !units.is_empty() => units.iter()
+                .map(|unit| {
+                    let split = unit.rfind(|c : char| c.is_ascii_digit()).map_or(0, |i| i + 1);
+                    parse_range(&unit[..split])
+                        .filter(|_| split < unit.len())
+                        .map(|(min, max)| (unit[split..].to_string(), min, max))
+                        .ok_or_else(|| format!("expected a range and a unit such as 150..=193cm, found {:?}", unit))
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(FieldType::Units),
+            ["color"] => Ok(FieldType::Color),
+            ["one-of", values @ ..] if !values.is_empty() =>
+                Ok(FieldType::OneOf(values.iter().map(|value| value.to_string()).collect())),
+            ["digits", length] => length.parse::<usize>()
+                .map(FieldType::Digits)
+                .map_err(|_| format!("expected a number of digits, found {:?}", length)),
+            _ => Err("expected one of any, range, units, color, one-of or digits".to_string()),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn check(&self, value : &str) -> bool {
+        self.violation(value).is_none()
+    }
+
+    //the rule the value breaks, worded for people
+    pub fn violation(&self, value : &str) -> Option<String> {
+        //numbers as they print, so `01937` or `+1937` is not a year, the typed passport reads the same values
+        let in_range = |s : &str, min : i64, max : i64| s.parse::<i64>().is_ok_and(|i| i >= min && i <= max && i.to_string() == s);
+        match self {
+            FieldType::Any => None,
+            FieldType::Range { min, max } if !in_range(value, *min, *max) => Some(format!("must be {}..={}", min, max)),
+            FieldType::Range { .. } => None,
+            FieldType::Units(units) => match units.iter().find(|(unit, _, _)| value.ends_with(unit.as_str())) {
+                Some((unit, min, max)) if !in_range(&value[..value.len() - unit.len()], *min, *max) =>
+                    Some(format!("must be {}..={} {}", min, max, unit)),
+                Some(_) => None,
+                None => Some(format!("must end in {}", units.iter().map(|(unit, _, _)| unit.as_str()).collect::<Vec<&str>>().join(" or "))),
+            },
+            FieldType::Color if value.len() == 7
+                && value.starts_with('#')
+                && value[1..].chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) => None,
+            FieldType::Color => Some("must be # followed by six lowercase hex digits".to_string()),
+            FieldType::OneOf(values) if values.iter().any(|v| v == value) => None,
+            FieldType::OneOf(values) => Some(format!("must be one of {}", values.join(", "))),
+            FieldType::Digits(length) if value.len() == *length && value.chars().all(|c| c.is_ascii_digit()) => None,
+            FieldType::Digits(length) => Some(format!("must be exactly {} digits", length)),
+        }
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldType::Any => write!(f, "any"),
+            FieldType::Range { min, max } => write!(f, "range {}..={}", min, max),
+            FieldType::Units(units) => {
+                write!(f, "units")?;
+                for (unit, min, max) in units {
+                    write!(f, " {}..={}{}", min, max, unit)?;
+                }
+                Ok(())
+            },
+            FieldType::Color => write!(f, "color"),
+            FieldType::OneOf(values) => write!(f, "one-of {}", values.join(" ")),
+            FieldType::Digits(length) => write!(f, "digits {}", length),
+        }
+    }
+}
+
+//one `key required|optional type` line per field, blank lines and lines starting with # are ignored
+impl FromStr for Schema {
+    type Err = SchemaParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        let mut fields : Vec<FieldRule> = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let error = |message : String| SchemaParseError { line: i + 1, message };
+            let tokens : Vec<&str> = line.split_whitespace().collect();
+            let (key, required, kind) = match tokens.as_slice() {
+                [] => continue,
+                [first, ..] if first.starts_with('#') => continue,
+                [key, required, kind @ ..] => (*key, *required, kind),
+                _ => return Err(error("expected a key, required or optional, and a type".to_string())),
+            };
+            let required = match required {
+                "required" => true,
+                "optional" => false,
+                _ => return Err(error(format!("expected required or optional, found {:?}", required))),
+            };
+            if fields.iter().any(|field| field.key == key) {
+                return Err(error(format!("field {} is declared twice", key)));
+            }
+            fields.push(FieldRule { key: key.to_string(), required, kind: FieldType::parse(kind).map_err(error)? });
+        }
+        Ok(Schema { fields })
+    }
+}
+
+impl Default for Schema {
+    fn default() -> Schema {
+        Schema::from_str(DEFAULT_SCHEMA).expect("the default schema is valid")
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{} {} {}", field.key, if field.required { "required" } else { "optional" }, field.kind)?;
+        }
+        Ok(())
+    }
+}
+
+impl Schema {
+    pub fn field(&self, key : &str) -> Option<&FieldRule> {
+        self.fields.iter().find(|field| field.key == key)
+    }
+
+    pub fn requiring(mut self, key : &str) -> Schema {
+        for field in self.fields.iter_mut().filter(|field| field.key == key) {
+            field.required = true;
+        }
+        self
+    }
+
+    //the same fields, but any value will do
+    pub fn presence_only(mut self) -> Schema {
+        for field in &mut self.fields {
+            field.kind = FieldType::Any;
+        }
+        self
+    }
+
+    pub fn declares(&self, key : &str) -> bool {
+        self.fields.iter().any(|field| field.key == key)
+    }
+
+    pub fn has_required_fields(&self, passport : &Passport) -> bool {
+        self.fields.iter()
+            .filter(|field| field.required)
+            .all(|field| passport.get(&field.key).is_some())
+    }
+
+    //required fields have to be there, and every field that is there has to match its type
+    pub fn validate(&self, passport : &Passport) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        for field in &self.fields {
+            match passport.get(&field.key) {
+                None if field.required => report.missing.push(field.key.clone()),
+                None => {},
+                Some(value) => if let Some(reason) = field.kind.violation(value) {
+                    report.invalid.push(FieldError { key: field.key.clone(), value: value.to_string(), reason });
+                },
+            }
+        }
+        report
+    }
+
+    pub fn is_valid(&self, passport : &Passport) -> bool {
+        self.validate(passport).is_valid()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_schema_round_trip() {
+        let schema = Schema::default();
+        assert_eq!(schema.fields.len(), 8);
+        assert_eq!(schema.field("hgt").unwrap().kind, FieldType::Units(vec![
+            ("cm".to_string(), 150, 193),
+            ("in".to_string(), 59, 76),
+        ]));
+        assert!(!schema.field("cid").unwrap().required);
+        assert_eq!(Schema::from_str(schema.to_string().as_str()), Ok(schema));
+    }
+
+    #[test]
+    fn schema_errors() {
+        assert_eq!(Schema::from_str("byr").unwrap_err().to_string(),
+            "Unable to parse schema at line 1: expected a key, required or optional, and a type");
+        assert_eq!(Schema::from_str("\nbyr maybe any").unwrap_err().to_string(),
+            "Unable to parse schema at line 2: expected required or optional, found \"maybe\"");
+        assert_eq!(Schema::from_str("byr required range 1920-2002").unwrap_err().to_string(),
+            "Unable to parse schema at line 1: expected a range such as 1920..=2002, found \"1920-2002\"");
+        assert_eq!(Schema::from_str("hgt required units 150..=193").unwrap_err().to_string(),
+            "Unable to parse schema at line 1: expected a range and a unit such as 150..=193cm, found \"150..=193\"");
+        assert_eq!(Schema::from_str("ecl required one-of").unwrap_err().to_string(),
+            "Unable to parse schema at line 1: expected one of any, range, units, color, one-of or digits");
+        assert_eq!(Schema::from_str("a optional any\na required color").unwrap_err().to_string(),
+            "Unable to parse schema at line 2: field a is declared twice");
+    }
+
+    #[test]
+    fn violations() {
+        let schema = Schema::default();
+        let violation = |key : &str, value : &str| schema.field(key).unwrap().kind.violation(value);
+        assert_eq!(violation("hgt", "190in"), Some("must be 59..=76 in".to_string()));
+        assert_eq!(violation("hgt", "190"), Some("must end in cm or in".to_string()));
+        assert_eq!(violation("hgt", "190cm"), None);
+        assert_eq!(violation("byr", "2003"), Some("must be 1920..=2002".to_string()));
+        assert_eq!(violation("byr", "01937"), Some("must be 1920..=2002".to_string()));
+        assert_eq!(violation("hgt", "+190cm"), Some("must be 150..=193 cm".to_string()));
+        assert_eq!(violation("hcl", "#ABCDEF"), Some("must be # followed by six lowercase hex digits".to_string()));
+        assert_eq!(violation("hcl", "123abc"), Some("must be # followed by six lowercase hex digits".to_string()));
+        assert_eq!(violation("ecl", "wat"), Some("must be one of amb, blu, brn, gry, grn, hzl, oth".to_string()));
+        assert_eq!(violation("pid", "0123456789"), Some("must be exactly 9 digits".to_string()));
+        assert_eq!(violation("cid", "anything"), None);
+    }
+
+    #[test]
+    fn new_fields_need_no_code() {
+        let schema = Schema::from_str("byr required range 1900..=2000\nzip required digits 5\nwgt optional units 40..=200kg").unwrap();
+        let passport = Passport::from_str("byr:1950 zip:01234").unwrap();
+        assert!(schema.is_valid(&passport));
+        assert!(!schema.is_valid(&Passport::from_str("byr:1950 zip:1234").unwrap()));
+        assert!(!schema.is_valid(&Passport::from_str("byr:1950 zip:01234 wgt:20kg").unwrap()));
+        assert!(!schema.has_required_fields(&Passport::from_str("byr:1950").unwrap()));
+    }
+}
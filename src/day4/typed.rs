@@ -0,0 +1,303 @@
+use super::Passport;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+//the shape of each field, whether the values are within the schema's ranges is another matter
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HeightUnit {
+    Centimeters,
+    Inches,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Height {
+    pub value : u32,
+    pub unit : HeightUnit,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HairColor {
+    pub red : u8,
+    pub green : u8,
+    pub blue : u8,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EyeColor {
+    Amber,
+    Blue,
+    Brown,
+    Gray,
+    Green,
+    Hazel,
+    Other,
+}
+
+//digits only, kept as text so leading zeros survive
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PassportId(String);
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TypedPassport {
+    pub birth_year : u16,
+    pub issue_year : u16,
+    pub expiration_year : u16,
+    pub height : Height,
+    pub hair_color : HairColor,
+    pub eye_color : EyeColor,
+    pub passport_id : PassportId,
+    pub country_id : Option<String>,
+    pub extra : BTreeMap<String, String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FieldParseError;
+
+#[derive(Debug, PartialEq)]
+pub enum TypedPassportError {
+    Missing(&'static str),
+    Invalid { key : &'static str, value : String },
+}
+
+impl Error for TypedPassportError {}
+impl fmt::Display for TypedPassportError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypedPassportError::Missing(key) => write!(f, "Unable to read passport: {} is missing", key),
+            TypedPassportError::Invalid { key, value } => write!(f, "Unable to read passport: {}={:?}", key, value),
+        }
+    }
+}
+
+fn digits(s : &str) -> Result<&str, FieldParseError> {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+        Ok(s)
+    } else {
+        Err(FieldParseError)
+    }
+}
+
+impl FromStr for Height {
+    type Err = FieldParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        let (value, unit) = if let Some(value) = s.strip_suffix("cm") {
+            (value, HeightUnit::Centimeters)
+        } else if let Some(value) = s.strip_suffix("in") {
+            (value, HeightUnit::Inches)
+        } else {
+            return Err(FieldParseError);
+        };
+        let value = digits(value)?.parse::<u32>().map_err(|_| FieldParseError)?;
+        Ok(Height { value, unit })
+    }
+}
+
+impl fmt::Display for Height {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        let unit = match self.unit {
+            HeightUnit::Centimeters => "cm",
+            HeightUnit::Inches => "in",
+        };
+        write!(f, "{}{}", self.value, unit)
+    }
+}
+
+impl FromStr for HairColor {
+    type Err = FieldParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').ok_or(FieldParseError)?;
+        if hex.len() != 6 || !hex.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+            return Err(FieldParseError);
+        }
+        let channel = |i : usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| FieldParseError);
+        Ok(HairColor { red: channel(0)?, green: channel(2)?, blue: channel(4)? })
+    }
+}
+
+impl fmt::Display for HairColor {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+const EYE_COLORS : [(EyeColor, &str); 7] = [
+    (EyeColor::Amber, "amb"),
+    (EyeColor::Blue, "blu"),
+    (EyeColor::Brown, "brn"),
+    (EyeColor::Gray, "gry"),
+    (EyeColor::Green, "grn"),
+    (EyeColor::Hazel, "hzl"),
+    (EyeColor::Other, "oth"),
+];
+
+impl FromStr for EyeColor {
+    type Err = FieldParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        EYE_COLORS.iter()
+            .find(|(_, code)| *code == s)
+            .map(|(color, _)| *color)
+            .ok_or(FieldParseError)
+    }
+}
+
+impl fmt::Display for EyeColor {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        let code = EYE_COLORS.iter()
+            .find(|(color, _)| color == self)
+            .map(|(_, code)| *code)
+            .unwrap_or("oth");
+        write!(f, "{}", code)
+    }
+}
+
+impl FromStr for PassportId {
+    type Err = FieldParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        Ok(PassportId(digits(s)?.to_string()))
+    }
+}
+
+impl fmt::Display for PassportId {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+//only the canonical spelling is accepted, so converting back gives the same text, `01937` or `0183cm` are invalid
+fn field<T : FromStr + fmt::Display>(passport : &Passport, key : &'static str) -> Result<T, TypedPassportError> {
+    let value = passport.get(key).ok_or(TypedPassportError::Missing(key))?;
+    value.parse::<T>().ok()
+        .filter(|parsed| parsed.to_string() == value)
+        .ok_or_else(|| TypedPassportError::Invalid { key, value: value.to_string() })
+}
+
+impl TryFrom<&Passport> for TypedPassport {
+    type Error = TypedPassportError;
+
+    fn try_from(passport : &Passport) -> Result<Self, Self::Error> {
+        Ok(TypedPassport {
+            birth_year: field(passport, "byr")?,
+            issue_year: field(passport, "iyr")?,
+            expiration_year: field(passport, "eyr")?,
+            height: field(passport, "hgt")?,
+            hair_color: field(passport, "hcl")?,
+            eye_color: field(passport, "ecl")?,
+            passport_id: field(passport, "pid")?,
+            country_id: passport.country_id.clone(),
+            extra: passport.extra.clone(),
+        })
+    }
+}
+
+impl From<&TypedPassport> for Passport {
+    fn from(passport : &TypedPassport) -> Passport {
+        Passport {
+            birth_year: Some(passport.birth_year.to_string()),
+            issue_year: Some(passport.issue_year.to_string()),
+            expiration_year: Some(passport.expiration_year.to_string()),
+            height: Some(passport.height.to_string()),
+            hair_color: Some(passport.hair_color.to_string()),
+            eye_color: Some(passport.eye_color.to_string()),
+            passport_id: Some(passport.passport_id.to_string()),
+            country_id: passport.country_id.clone(),
+            extra: passport.extra.clone(),
+        }
+    }
+}
+
+impl fmt::Display for TypedPassport {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Passport::from(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::schema::Schema;
+
+    const SAMPLE : &str = "ecl:gry pid:060033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm zip:01234";
+
+    #[test]
+    fn typed_fields() {
+        let typed = TypedPassport::try_from(&Passport::from_str(SAMPLE).unwrap()).unwrap();
+        assert_eq!(typed.birth_year, 1937);
+        assert_eq!(typed.height, Height { value: 183, unit: HeightUnit::Centimeters });
+        assert_eq!(typed.hair_color, HairColor { red: 255, green: 255, blue: 253 });
+        assert_eq!(typed.eye_color, EyeColor::Gray);
+        assert_eq!(typed.passport_id.to_string(), "060033327");
+        assert_eq!(typed.extra.get("zip").map(String::as_str), Some("01234"));
+    }
+
+    #[test]
+    fn canonical_round_trip() {
+        let raw = Passport::from_str(SAMPLE).unwrap();
+        let typed = TypedPassport::try_from(&raw).unwrap();
+        let printed = typed.to_string();
+        assert_eq!(printed, "byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry pid:060033327 cid:147 zip:01234");
+        let reparsed = Passport::from_str(printed.as_str()).unwrap();
+        assert_eq!(reparsed, raw);
+        assert_eq!(TypedPassport::try_from(&reparsed).unwrap(), typed);
+        assert_eq!(raw.to_string(), printed);
+    }
+
+    #[test]
+    fn non_canonical_values_are_rejected() {
+        let canonical = "byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#a97842 ecl:gry pid:060033327";
+        for (from, to) in &[("byr:1937", "byr:01937"), ("hgt:183cm", "hgt:0183cm"), ("hcl:#a97842", "hcl:#A97842")] {
+            let raw = Passport::from_str(canonical.replace(from, to).as_str()).unwrap();
+            let key = &to[..3];
+            assert_eq!(TypedPassport::try_from(&raw), Err(TypedPassportError::Invalid { key, value: to[4..].to_string() }));
+        }
+        //whatever converts comes back exactly as it was
+        let raw = Passport::from_str(canonical).unwrap();
+        assert_eq!(Passport::from(&TypedPassport::try_from(&raw).unwrap()), raw);
+    }
+
+    #[test]
+    fn typed_and_validated_agree() {
+        let schema = Schema::default();
+        let valid = "byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#abcdef ecl:gry pid:060033327";
+        let raw = Passport::from_str(valid).unwrap();
+        assert!(schema.is_valid(&raw) && TypedPassport::try_from(&raw).is_ok());
+        for (from, to) in &[("hcl:#abcdef", "hcl:#ABCDEF"), ("hcl:#abcdef", "hcl:#aBcdef"), ("byr:1937", "byr:01937"),
+            ("iyr:2017", "iyr:+2017"), ("hgt:183cm", "hgt:0183cm")] {
+            let raw = Passport::from_str(valid.replace(from, to).as_str()).unwrap();
+            assert!(!schema.is_valid(&raw), "{}", to);
+            assert!(TypedPassport::try_from(&raw).is_err(), "{}", to);
+        }
+    }
+
+    #[test]
+    fn conversion_errors() {
+        let error = |input : &str| TypedPassport::try_from(&Passport::from_str(input).unwrap()).unwrap_err().to_string();
+        assert_eq!(error("iyr:2017"), "Unable to read passport: byr is missing");
+        assert_eq!(error("byr:19x7"), "Unable to read passport: byr=\"19x7\"");
+        assert_eq!(error("byr:+1937"), "Unable to read passport: byr=\"+1937\"");
+        assert_eq!(error("byr:1 iyr:2 eyr:3 hgt:170"), "Unable to read passport: hgt=\"170\"");
+        assert_eq!(error("byr:1 iyr:2 eyr:3 hgt:1cm hcl:#12345g"), "Unable to read passport: hcl=\"#12345g\"");
+        assert_eq!(error("byr:1 iyr:2 eyr:3 hgt:1cm hcl:#123456 ecl:red"), "Unable to read passport: ecl=\"red\"");
+        assert_eq!(error("byr:1 iyr:2 eyr:3 hgt:1cm hcl:#123456 ecl:oth pid:12a"), "Unable to read passport: pid=\"12a\"");
+        //out of range for the schema, but well formed
+        assert!(TypedPassport::try_from(&Passport::from_str("byr:1 iyr:2 eyr:3 hgt:1in hcl:#123456 ecl:oth pid:1").unwrap()).is_ok());
+    }
+
+    #[test]
+    fn field_round_trips() {
+        for height in &["0cm", "76in", "193cm"] {
+            assert_eq!(Height::from_str(height).unwrap().to_string(), *height);
+        }
+        for (_, code) in EYE_COLORS.iter() {
+            assert_eq!(EyeColor::from_str(code).unwrap().to_string(), *code);
+        }
+        assert_eq!(HairColor::from_str("#a97842").unwrap().to_string(), "#a97842");
+        assert_eq!(HairColor::from_str("#A97842"), Err(FieldParseError));
+        assert_eq!(Height::from_str("cm"), Err(FieldParseError));
+    }
+}
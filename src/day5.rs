@@ -1,123 +1,52 @@
+mod layout;
+
 use crate::inputs::read_input;
+use crate::options::Options;
+use layout::Layout;
 use std::str::FromStr;
 use std::{error::Error, fmt};
 
 #[cfg(test)]
 use rstest::rstest;
 
-#[derive(Debug, PartialEq, Clone)]
-enum RowSelector {
-    Front,
-    Back,
-}
-impl RowSelector {
-    fn from_str(o : Option<char>) -> RowSelector {
-        match o {
-            Some('B') => RowSelector::Back,
-            Some('F') => RowSelector::Front,
-            _ => panic!("invalid column selector: {:?}", o),
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Clone)]
-enum ColumnSelector {
-    Left,
-    Right,
-}
-
-impl ColumnSelector {
-    fn from_str(o : Option<char>) -> ColumnSelector {
-        match o {
-            Some('L') => ColumnSelector::Left,
-            Some('R') => ColumnSelector::Right,
-            _ => panic!("invalid column selector: {:?}", o),
-        }
-    }
-}
-
 #[derive(Debug, PartialEq, Clone)]
 struct Seat {
-    columns : [ColumnSelector; 3],
-    rows : [RowSelector; 7],
+    row : usize,
+    column : usize,
+    id : usize,
 }
 
-#[derive(Debug)]
-struct SeatParseError;
+#[derive(Debug, PartialEq)]
+struct SeatParseError {
+    pass : String,
+    reason : String,
+}
 
 impl Error for SeatParseError {}
 impl fmt::Display for SeatParseError {
     fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Unable to parse seat")
+        write!(f, "Unable to parse seat {:?}: {}", self.pass, self.reason)
     }
 }
 
 impl Seat {
     fn seat_id(&self) -> usize {
-        self.row() * 8 + self.column()
+        self.id
     }
+    #[cfg(test)]
     fn row(&self) -> usize {
-        let mut min = 0;
-        let mut max = 127;
-        //println!("row min={}, max={}", min, max);
-        for i in 0..self.rows.len() {
-            let half = min + (max - min) / 2;
-            let (local_min, local_max) = if self.rows[i] == RowSelector::Front {
-                (min, half)
-            } else {
-                (half, max)
-            };
-            max = local_max;
-            min = local_min;
-            //println!("row min={}, max={} param={:?}", min, max, self.rows[i]);
-        }
-        max
+        self.row
     }
+    #[cfg(test)]
     fn column(&self) -> usize {
-        let mut min = 0;
-        let mut max = 7;
-        //println!("column min={}, max={}", min, max);
-        for i in 0..self.columns.len() {
-            let half = min + (max - min) / 2;
-            let (local_min, local_max) = if self.columns[i] == ColumnSelector::Left {
-                (min, half)
-            } else {
-                (half, max)
-            };
-            max = local_max;
-            min = local_min;
-            //println!("column min={}, max={} param={:?}", min, max, self.columns[i]);
-        }
-        max
+        self.column
     }
 }
 impl FromStr for Seat {
     type Err = SeatParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        //println!("parsing {}", s);
-        let rows = &s[0..7];
-        let columns = &s[7..10];
-
-        //println!("columns: {}", columns);
-        //println!("rows: {}", rows);
-
-        Ok(Seat{
-            columns: [
-                ColumnSelector::from_str(columns.chars().nth(0)),
-                ColumnSelector::from_str(columns.chars().nth(1)),
-                ColumnSelector::from_str(columns.chars().nth(2)),
-            ],
-            rows: [
-                RowSelector::from_str(rows.chars().nth(0)),
-                RowSelector::from_str(rows.chars().nth(1)),
-                RowSelector::from_str(rows.chars().nth(2)),
-                RowSelector::from_str(rows.chars().nth(3)),
-                RowSelector::from_str(rows.chars().nth(4)),
-                RowSelector::from_str(rows.chars().nth(5)),
-                RowSelector::from_str(rows.chars().nth(6)),
-            ]
-        })
+        Layout::default().decode(s)
     }
 }
 
@@ -129,36 +58,37 @@ impl FromStr for Seats {
     type Err = SeatParseError;
 
     fn from_str(s : &str) -> Result<Self, Self::Err> {
+        Seats::parse(s, &Layout::default())
+    }
+}
+
+impl Seats {
+    fn parse(s : &str, layout : &Layout) -> Result<Seats, SeatParseError> {
         let mut seats : Vec<Seat> = vec![];
-        for line in s.split("\n") {
+        for line in s.split('\n') {
             if !line.is_empty() {
-                let seat = Seat::from_str(line)?;
+                let seat = layout.decode(line)?;
                 seats.push(seat);
             }
         }
         Ok(Seats {
-            seats: seats,
+            seats,
         })
     }
-}
 
-impl Seats {
     fn len(&self) -> usize {
         self.seats.len()
     }
 
-    fn from_input() -> Result<Seats, Box<dyn std::error::Error>> {
+    fn from_input(layout : &Layout) -> Result<Seats, Box<dyn std::error::Error>> {
         let contents = read_input(5)?;
-        let seats = Seats::from_str(contents.as_str())?;
+        let seats = Seats::parse(contents.as_str(), layout)?;
         println!("found {} seats", seats.len());
         Ok(seats)
     }
 
     fn highest_seat_id(&self) -> usize {
-        match self.seats.iter().map(Seat::seat_id).max() {
-            Some(x) => x,
-            None => 0,
-        }
+        self.seats.iter().map(Seat::seat_id).max().unwrap_or(0)
     }
 
     fn my_seat(&self) -> Vec<usize> {
@@ -168,32 +98,45 @@ impl Seats {
         let highest_seat_id = self.highest_seat_id();
         (0..=highest_seat_id)
             .filter(|seat_id| !all_seat_ids.contains(seat_id))
-            .filter(|seat_id| all_seat_ids.contains(&(*seat_id + 1)) && all_seat_ids.contains(&(*seat_id - 1)))
+            .filter(|seat_id| *seat_id > 0 && all_seat_ids.contains(&(*seat_id + 1)) && all_seat_ids.contains(&(*seat_id - 1)))
             .collect()
     }
 }
 
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::from_env();
+    //`--layout 7FB/3LR*8` reads passes for another aircraft, the puzzle's is `standard`
+    let layout = options.parse::<Layout>("layout")?.unwrap_or_default();
 
-    let seats = Seats::from_input()?;
+    let seats = Seats::from_input(&layout)?;
     println!("highest seat_id: {}", seats.highest_seat_id());
 
     let my_seat = seats.my_seat();
     println!("my seat: {:?}", my_seat);
+    for seat_id in &my_seat {
+        println!("boarding pass for seat {}: {}", seat_id, layout.encode_id(*seat_id)?);
+    }
+
+    //`--encode 617 77,1` issues passes for seat ids or row,column pairs
+    for seat in options.get_all("encode") {
+        let pass = match seat.split_once(',') {
+            Some((row, column)) => layout.encode(row.parse::<usize>()?, column.parse::<usize>()?)?,
+            None => layout.encode_id(seat.parse::<usize>()?)?,
+        };
+        println!("{}: {}", seat, pass);
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ColumnSelector::*;
-    use RowSelector::*;
 
     #[test]
     fn parse_seat() {
         let seat = Seat::from_str("BFFFBBFRRR").unwrap();
-        assert_eq!(seat.columns, [Right, Right, Right]);
-        assert_eq!(seat.rows, [Back, Front, Front, Front, Back, Back, Front]);
+        assert_eq!(seat, Seat { row: 0b1000110, column: 0b111, id: 567 });
+        assert_eq!(Seat::from_str("BFFFBBFRRX").unwrap_err().to_string(), "Unable to parse seat \"BFFFBBFRRX\": columns are L or R");
     }
 
     #[rstest(location, row, column, seat_id,
@@ -224,7 +167,7 @@ BBFFBBFRLL").unwrap();
 
     #[test]
     fn my_seat() {
-        let seats = Seats::from_input().unwrap();
+        let seats = Seats::from_input(&Layout::default()).unwrap();
         let my_seat = seats.my_seat();
 
         assert_eq!(my_seat, vec![617]);
@@ -0,0 +1,234 @@
+use super::{Seat, SeatParseError};
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+//one half of a pass, each character picks the lower or the upper half, so it reads as a binary number
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Axis {
+    pub bits : u32,
+    pub lower : char,
+    pub upper : char,
+}
+
+impl Axis {
+    pub fn size(&self) -> usize {
+        1 << self.bits
+    }
+
+    fn decode(&self, s : &str) -> Option<usize> {
+        s.chars().try_fold(0, |value, c| match c {
+            c if c == self.lower => Some(value << 1),
+            c if c == self.upper => Some(value << 1 | 1),
+            _ => None,
+        })
+    }
+
+    fn encode(&self, value : usize) -> String {
+        (0..self.bits).rev()
+            .map(|bit| if value >> bit & 1 == 1 { self.upper } else { self.lower })
+            .collect()
+    }
+}
+
+//rows first, then columns, and seat id = row * id_multiplier + column
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Layout {
+    pub rows : Axis,
+    pub columns : Axis,
+    pub id_multiplier : usize,
+}
+
+//the puzzle's 128 rows of 8 seats
+impl Default for Layout {
+    fn default() -> Layout {
+        Layout {
+            rows: Axis { bits: 7, lower: 'F', upper: 'B' },
+            columns: Axis { bits: 3, lower: 'L', upper: 'R' },
+            id_multiplier: 8,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SeatEncodeError {
+    OutOfRange { row : usize, column : usize, layout : Layout },
+    //a layout built by hand can still have fewer ids per row than columns, 0 included
+    AmbiguousId { id : usize, layout : Layout },
+}
+
+impl Error for SeatEncodeError {}
+impl fmt::Display for SeatEncodeError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SeatEncodeError::OutOfRange { row, column, layout } =>
+                write!(f, "Unable to encode seat at row {}, column {}: the layout has {} rows of {} seats",
+                    row, column, layout.rows.size(), layout.columns.size()),
+            SeatEncodeError::AmbiguousId { id, layout } =>
+                write!(f, "Unable to encode seat id {}: an id multiplier of {} doesn't give each of the {} columns its own id",
+                    id, layout.id_multiplier, layout.columns.size()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LayoutParseError {
+    input : String,
+}
+
+impl Error for LayoutParseError {}
+impl fmt::Display for LayoutParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse layout {:?}, expected standard or something like 7FB/3LR*8", self.input)
+    }
+}
+
+fn parse_axis(s : &str) -> Option<Axis> {
+    let mut chars : Vec<char> = s.chars().collect();
+    let upper = chars.pop()?;
+    let lower = chars.pop()?;
+    let bits = chars.into_iter().collect::<String>().parse::<u32>().ok()?;
+    Some(Axis { bits, lower, upper }).filter(|axis| axis.lower != axis.upper && axis.bits < usize::BITS / 2)
+}
+
+//`standard`, or `<row bits><lower><upper>/<column bits><lower><upper>` with an optional `*<id multiplier>`,
+//which defaults to the number of columns and can't be any smaller
+impl FromStr for Layout {
+    type Err = LayoutParseError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        if s == "standard" {
+            return Ok(Layout::default());
+        }
+        let error = || LayoutParseError { input: s.to_string() };
+        let (axes, multiplier) = match s.split_once('*') {
+            Some((axes, multiplier)) => (axes, Some(multiplier.parse::<usize>().map_err(|_| error())?)),
+            None => (s, None),
+        };
+        let (rows, columns) = axes.split_once('/').ok_or_else(error)?;
+        let rows = parse_axis(rows).ok_or_else(error)?;
+        let columns = parse_axis(columns).ok_or_else(error)?;
+        //the row characters have to tell rows and columns apart
+        if [rows.lower, rows.upper].iter().any(|c| *c == columns.lower || *c == columns.upper) {
+            return Err(error());
+        }
+        //with fewer than one id per column, seats in neighbouring rows would share ids
+        let id_multiplier = multiplier.unwrap_or(columns.size());
+        if id_multiplier < columns.size() {
+            return Err(error());
+        }
+        Ok(Layout { rows, columns, id_multiplier })
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}{}/{}{}{}*{}", self.rows.bits, self.rows.lower, self.rows.upper,
+            self.columns.bits, self.columns.lower, self.columns.upper, self.id_multiplier)
+    }
+}
+
+impl Layout {
+    pub fn pass_length(&self) -> usize {
+        (self.rows.bits + self.columns.bits) as usize
+    }
+
+    //None when a large multiplier takes the id past usize
+    pub fn seat_id(&self, row : usize, column : usize) -> Option<usize> {
+        row.checked_mul(self.id_multiplier)?.checked_add(column)
+    }
+
+    pub fn decode(&self, pass : &str) -> Result<Seat, SeatParseError> {
+        let error = |reason : String| SeatParseError { pass: pass.to_string(), reason };
+        if pass.chars().count() != self.pass_length() {
+            return Err(error(format!("expected {} characters", self.pass_length())));
+        }
+        let split = pass.char_indices().nth(self.rows.bits as usize).map_or(pass.len(), |(i, _)| i);
+        let row = self.rows.decode(&pass[..split])
+            .ok_or_else(|| error(format!("rows are {} or {}", self.rows.lower, self.rows.upper)))?;
+        let column = self.columns.decode(&pass[split..])
+            .ok_or_else(|| error(format!("columns are {} or {}", self.columns.lower, self.columns.upper)))?;
+        let id = self.seat_id(row, column).ok_or_else(|| error("seat id is too large".to_string()))?;
+        Ok(Seat { row, column, id })
+    }
+
+    pub fn encode(&self, row : usize, column : usize) -> Result<String, SeatEncodeError> {
+        if row >= self.rows.size() || column >= self.columns.size() {
+            return Err(SeatEncodeError::OutOfRange { row, column, layout: *self });
+        }
+        Ok(self.rows.encode(row) + &self.columns.encode(column))
+    }
+
+    //the only row and column that give the id
+    pub fn encode_id(&self, id : usize) -> Result<String, SeatEncodeError> {
+        if self.id_multiplier < self.columns.size() {
+            return Err(SeatEncodeError::AmbiguousId { id, layout: *self });
+        }
+        self.encode(id / self.id_multiplier, id % self.id_multiplier)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_layouts() {
+        assert_eq!(Layout::from_str("standard").unwrap(), Layout::default());
+        assert_eq!(Layout::from_str("7FB/3LR").unwrap(), Layout::default());
+        let wide = Layout::from_str("5ud/4<>*100").unwrap();
+        assert_eq!(wide.to_string(), "5ud/4<>*100");
+        assert_eq!((wide.rows.size(), wide.columns.size()), (32, 16));
+        for bad in &["7FF/3LR", "7FB", "xFB/3LR", "7FB/3LB", "7FB/3LR*", "7FB/3LR*0", "7FB/3LR*5", "40FB/3LR"] {
+            assert!(Layout::from_str(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn decode_other_layouts() {
+        let layout = Layout::from_str("2ud/3<>*10").unwrap();
+        let seat = layout.decode("du><>").unwrap();
+        assert_eq!((seat.row(), seat.column(), seat.seat_id()), (2, 5, 25));
+        assert_eq!(layout.decode("du><").unwrap_err().to_string(), "Unable to parse seat \"du><\": expected 5 characters");
+        assert_eq!(layout.decode("d<><>").unwrap_err().to_string(), "Unable to parse seat \"d<><>\": rows are u or d");
+        assert_eq!(layout.decode("ddLRL").unwrap_err().to_string(), "Unable to parse seat \"ddLRL\": columns are < or >");
+        let huge = Layout::from_str(&format!("2ud/3<>*{}", usize::MAX)).unwrap();
+        assert_eq!(huge.decode("uu>>>").unwrap().seat_id(), 7);
+        assert_eq!(huge.decode("du<<<").unwrap_err().to_string(), "Unable to parse seat \"du<<<\": seat id is too large");
+    }
+
+    #[test]
+    fn encode_seats() {
+        let layout = Layout::default();
+        assert_eq!(layout.encode(70, 7), Ok("BFFFBBFRRR".to_string()));
+        assert_eq!(layout.encode_id(357), Ok("FBFBBFFRLR".to_string()));
+        assert_eq!(layout.encode(128, 0).unwrap_err().to_string(),
+            "Unable to encode seat at row 128, column 0: the layout has 128 rows of 8 seats");
+        assert!(layout.encode_id(1024).is_err());
+        //a multiplier wider than the rows leaves ids without a seat
+        let spaced = Layout::from_str("2FB/2LR*10").unwrap();
+        assert_eq!(spaced.encode_id(23), Ok("BFRR".to_string()));
+        assert!(spaced.encode_id(24).is_err());
+        let zero = Layout { id_multiplier: 0, ..Layout::default() };
+        assert_eq!(zero.encode_id(5).unwrap_err().to_string(),
+            "Unable to encode seat id 5: an id multiplier of 0 doesn't give each of the 8 columns its own id");
+        //row 0 column 5 and row 1 column 0 would both be 5
+        let narrow = Layout { id_multiplier: 5, ..Layout::default() };
+        assert_eq!(narrow.encode_id(5), Err(SeatEncodeError::AmbiguousId { id: 5, layout: narrow }));
+    }
+
+    #[test]
+    fn round_trip_every_seat() {
+        for layout in &["standard", "2ud/3<>*10", "4FB/1LR", "1FB/6LR*64", "9FB/2LR*5"] {
+            let layout = Layout::from_str(layout).unwrap();
+            for row in 0..layout.rows.size() {
+                for column in 0..layout.columns.size() {
+                    let pass = layout.encode(row, column).unwrap();
+                    let seat = layout.decode(&pass).unwrap();
+                    assert_eq!((seat.row(), seat.column()), (row, column), "{} {}", layout, pass);
+                    assert_eq!(seat.seat_id(), row * layout.id_multiplier + column);
+                    assert_eq!(layout.encode_id(seat.seat_id()), Ok(pass));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+//a signed point or vector, with y growing downwards like the rows of a map
+#[derive(Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Copy, Clone, Default)]
+pub struct Point {
+    pub x : i64,
+    pub y : i64,
+}
+
+impl Point {
+    pub const ORIGIN : Point = Point::new(0, 0);
+    pub const UP : Point = Point::new(0, -1);
+    pub const DOWN : Point = Point::new(0, 1);
+    pub const LEFT : Point = Point::new(-1, 0);
+    pub const RIGHT : Point = Point::new(1, 0);
+    pub const UP_LEFT : Point = Point::new(-1, -1);
+    pub const UP_RIGHT : Point = Point::new(1, -1);
+    pub const DOWN_LEFT : Point = Point::new(-1, 1);
+    pub const DOWN_RIGHT : Point = Point::new(1, 1);
+
+    //clockwise, starting up
+    pub const DIRECTIONS_8 : [Point; 8] = [
+        Point::UP, Point::UP_RIGHT, Point::RIGHT, Point::DOWN_RIGHT,
+        Point::DOWN, Point::DOWN_LEFT, Point::LEFT, Point::UP_LEFT,
+    ];
+
+    pub const fn new(x : i64, y : i64) -> Point {
+        Point { x, y }
+    }
+
+    pub fn manhattan(self, other : Point) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+}
+
+//turning and four-way moves for maps walked a step at a time, no day walks one yet
+#[allow(dead_code)]
+impl Point {
+    //clockwise, starting up
+    pub const DIRECTIONS_4 : [Point; 4] = [Point::UP, Point::RIGHT, Point::DOWN, Point::LEFT];
+
+    //90 degrees clockwise as seen on screen, so RIGHT becomes DOWN
+    pub fn rotate_right(self) -> Point {
+        Point::new(-self.y, self.x)
+    }
+
+    //90 degrees counter-clockwise as seen on screen, so RIGHT becomes UP
+    pub fn rotate_left(self) -> Point {
+        Point::new(self.y, -self.x)
+    }
+
+    //king moves, so diagonal neighbors are 1 away
+    pub fn chebyshev(self, other : Point) -> i64 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, other : Point) -> Point {
+        Point::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, other : Point) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, other : Point) -> Point {
+        Point::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, other : Point) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<i64> for Point {
+    type Output = Point;
+
+    fn mul(self, scalar : i64) -> Point {
+        Point::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl Neg for Point {
+    type Output = Point;
+
+    fn neg(self) -> Point {
+        Point::new(-self.x, -self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = Point::new(3, 1);
+        let b = Point::new(-1, 2);
+        assert_eq!(a + b, Point::new(2, 3));
+        assert_eq!(a - b, Point::new(4, -1));
+        assert_eq!(b - a, Point::new(-4, 1));
+        assert_eq!(a * 3, Point::new(9, 3));
+        assert_eq!(-a, Point::new(-3, -1));
+
+        let mut c = a;
+        c += b;
+        c -= a * 2;
+        assert_eq!(c, Point::new(-4, 1));
+    }
+
+    #[test]
+    fn rotations() {
+        assert_eq!(Point::RIGHT.rotate_right(), Point::DOWN);
+        assert_eq!(Point::RIGHT.rotate_left(), Point::UP);
+        assert_eq!(Point::UP_RIGHT.rotate_right(), Point::DOWN_RIGHT);
+        let p = Point::new(3, -7);
+        assert_eq!(p.rotate_left().rotate_right(), p);
+        assert_eq!(p.rotate_right().rotate_right(), -p);
+        assert_eq!(p.rotate_right().rotate_right().rotate_right().rotate_right(), p);
+        for (i, direction) in Point::DIRECTIONS_4.iter().enumerate() {
+            assert_eq!(direction.rotate_right(), Point::DIRECTIONS_4[(i + 1) % 4]);
+        }
+    }
+
+    #[test]
+    fn distances() {
+        let a = Point::new(1, -2);
+        let b = Point::new(-3, 4);
+        assert_eq!(a.manhattan(b), 10);
+        assert_eq!(a.chebyshev(b), 6);
+        assert_eq!(Point::ORIGIN.manhattan(Point::ORIGIN), 0);
+        assert_eq!(Point::ORIGIN.chebyshev(Point::ORIGIN), 0);
+        //clockwise from up, the diagonals are two steps for manhattan and one for chebyshev
+        let manhattan : Vec<i64> = Point::DIRECTIONS_8.iter().map(|d| Point::ORIGIN.manhattan(*d)).collect();
+        assert_eq!(manhattan, vec![1, 2, 1, 2, 1, 2, 1, 2]);
+        for direction in Point::DIRECTIONS_8.iter() {
+            assert_eq!(Point::ORIGIN.chebyshev(*direction), 1);
+        }
+        for direction in Point::DIRECTIONS_4.iter() {
+            assert_eq!(Point::ORIGIN.manhattan(*direction), 1);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use crate::geometry::Point;
+use std::{error::Error, fmt};
+
+//how coordinates outside of the grid are mapped back onto it, the forest only needs the cylinder
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[allow(dead_code)]
+pub enum Topology {
+    Bounded,
+    //wraps horizontally only
+    Cylinder,
+    //wraps horizontally and vertically
+    Torus,
+}
+
+//which cells count as neighbors, no day walks a grid cell by cell yet
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[allow(dead_code)]
+pub enum Connectivity {
+    Four,
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(&self) -> &'static [Point] {
+        match self {
+            Connectivity::Four => &Point::DIRECTIONS_4,
+            Connectivity::Eight => &Point::DIRECTIONS_8,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum GridParseError {
+    Empty,
+    RaggedRow { line : usize, expected : usize, found : usize },
+}
+
+impl Error for GridParseError {}
+impl fmt::Display for GridParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridParseError::Empty => write!(f, "Unable to parse grid: no rows"),
+            GridParseError::RaggedRow { line, expected, found } =>
+                write!(f, "Unable to parse grid: line {} has {} cells, expected {}", line, found, expected),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Grid<T> {
+    cells : Vec<T>,
+    width : usize,
+    height : usize,
+    topology : Topology,
+}
+
+fn wrap_axis(value : i64, size : usize, wraps : bool) -> Option<i64> {
+    if wraps {
+        Some(value.rem_euclid(size as i64))
+    } else if value >= 0 && value < size as i64 {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+impl<T> Grid<T> {
+    //row by row, the number of cells has to be a multiple of the width
+    pub fn from_cells(cells : Vec<T>, width : usize, topology : Topology) -> Result<Grid<T>, GridParseError> {
+        if width == 0 || cells.is_empty() {
+            return Err(GridParseError::Empty);
+        }
+        if !cells.len().is_multiple_of(width) {
+            let height = cells.len() / width;
+            return Err(GridParseError::RaggedRow { line: height + 1, expected: width, found: cells.len() % width });
+        }
+        Ok(Grid { height: cells.len() / width, cells, width, topology })
+    }
+
+    //maps a point onto the grid according to its topology
+    pub fn wrap(&self, p : Point) -> Option<Point> {
+        let (wrap_x, wrap_y) = match self.topology {
+            Topology::Bounded => (false, false),
+            Topology::Cylinder => (true, false),
+            Topology::Torus => (true, true),
+        };
+        Some(Point::new(wrap_axis(p.x, self.width, wrap_x)?, wrap_axis(p.y, self.height, wrap_y)?))
+    }
+
+    pub fn get(&self, p : Point) -> Option<&T> {
+        let p = self.wrap(p)?;
+        self.cells.get(p.y as usize * self.width + p.x as usize)
+    }
+}
+
+//the forest builds its terrain from cells it has already read and only looks up squares,
+//the rest is here for maps that are read and walked as a whole
+#[allow(dead_code)]
+impl<T> Grid<T> {
+    //parses a char map, one row per non-empty line, from_cells can only check the total so rows are checked here
+    pub fn parse<F>(s : &str, topology : Topology, f : F) -> Result<Grid<T>, GridParseError> where F : Fn(char) -> T {
+        let mut cells = vec![];
+        let mut width = None;
+        for (i, line) in s.split('\n').enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            if line.is_empty() {
+                continue;
+            }
+            let before = cells.len();
+            cells.extend(line.chars().map(&f));
+            let found = cells.len() - before;
+            let expected = *width.get_or_insert(found);
+            if found != expected {
+                return Err(GridParseError::RaggedRow { line: i + 1, expected, found });
+            }
+        }
+        Grid::from_cells(cells, width.unwrap_or(0), topology)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn row(&self, y : usize) -> Option<&[T]> {
+        if y < self.height {
+            Some(&self.cells[y * self.width..(y + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn column(&self, x : usize) -> impl Iterator<Item = &T> {
+        let width = self.width;
+        self.cells.iter()
+            .skip(x)
+            .step_by(width)
+            .take(if x < width { self.height } else { 0 })
+    }
+
+    //the neighbors of a cell, after wrapping
+    pub fn neighbors(&self, p : Point, connectivity : Connectivity) -> impl Iterator<Item = Point> + '_ {
+        connectivity.offsets().iter()
+            .filter_map(move |offset| self.wrap(p + *offset))
+    }
+}
+
+//writes the grid back as the char map it was parsed from
+impl<T> fmt::Display for Grid<T> where T : Copy + Into<char> {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for cell in row {
+                write!(f, "{}", Into::<char>::into(*cell))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(topology : Topology) -> Grid<char> {
+        Grid::parse("123\n456\n", topology, |c| c).unwrap()
+    }
+
+    #[test]
+    fn parse_grid() {
+        let grid = digits(Topology::Bounded);
+        assert_eq!((grid.width(), grid.height(), grid.topology()), (3, 2, Topology::Bounded));
+        assert_eq!(grid.get(Point::new(1, 0)), Some(&'2'));
+        assert_eq!(grid.rows().collect::<Vec<&[char]>>(), vec![&['1', '2', '3'][..], &['4', '5', '6'][..]]);
+        assert_eq!(grid.row(1), Some(&['4', '5', '6'][..]));
+        assert_eq!(grid.row(2), None);
+        assert_eq!(grid.column(1).collect::<String>(), "25");
+        assert_eq!(grid.column(3).count(), 0);
+        assert_eq!(grid.to_string(), "123\n456");
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Grid::parse("\n\n", Topology::Bounded, |c| c), Err(GridParseError::Empty));
+        assert_eq!(Grid::parse("123\n\n45", Topology::Bounded, |c| c),
+            Err(GridParseError::RaggedRow { line: 3, expected: 3, found: 2 }));
+    }
+
+    #[test]
+    fn from_cells() {
+        assert_eq!(Grid::from_cells("123456".chars().collect(), 3, Topology::Bounded), Ok(digits(Topology::Bounded)));
+        assert_eq!(Grid::from_cells(vec!['1'], 0, Topology::Bounded), Err(GridParseError::Empty));
+        assert_eq!(Grid::<char>::from_cells(vec![], 3, Topology::Bounded), Err(GridParseError::Empty));
+        assert_eq!(Grid::from_cells("12345".chars().collect(), 3, Topology::Bounded),
+            Err(GridParseError::RaggedRow { line: 2, expected: 3, found: 2 }));
+    }
+
+    #[test]
+    fn topologies() {
+        let bounded = digits(Topology::Bounded);
+        assert_eq!(bounded.get(Point::new(2, 1)), Some(&'6'));
+        assert_eq!(bounded.get(Point::new(3, 0)), None);
+        assert_eq!(bounded.get(Point::new(-1, 0)), None);
+
+        let cylinder = digits(Topology::Cylinder);
+        assert_eq!(cylinder.get(Point::new(3, 0)), Some(&'1'));
+        assert_eq!(cylinder.get(Point::new(-1, 1)), Some(&'6'));
+        assert_eq!(cylinder.get(Point::new(0, 2)), None);
+
+        let torus = digits(Topology::Torus);
+        assert_eq!(torus.get(Point::new(-1, -1)), Some(&'6'));
+        assert_eq!(torus.get(Point::new(4, 5)), Some(&'5'));
+    }
+
+    #[test]
+    fn neighbors() {
+        let bounded = digits(Topology::Bounded);
+        assert_eq!(bounded.neighbors(Point::ORIGIN, Connectivity::Four).collect::<Vec<_>>(),
+            vec![Point::new(1, 0), Point::new(0, 1)]);
+        assert_eq!(bounded.neighbors(Point::ORIGIN, Connectivity::Eight).collect::<Vec<_>>(),
+            vec![Point::new(1, 0), Point::new(1, 1), Point::new(0, 1)]);
+
+        let cylinder = digits(Topology::Cylinder);
+        assert_eq!(cylinder.neighbors(Point::ORIGIN, Connectivity::Four).collect::<Vec<_>>(),
+            vec![Point::new(1, 0), Point::new(0, 1), Point::new(2, 0)]);
+
+        let torus = digits(Topology::Torus);
+        assert_eq!(torus.neighbors(Point::new(1, 0), Connectivity::Eight).count(), 8);
+    }
+}
@@ -0,0 +1,178 @@
+pub type Rgb = [u8; 3];
+
+//a plain RGB image, written out as binary PPM or as an uncompressed PNG
+#[derive(Debug, PartialEq, Clone)]
+pub struct Image {
+    width : usize,
+    height : usize,
+    pixels : Vec<Rgb>,
+}
+
+impl Image {
+    pub fn new(width : usize, height : usize, background : Rgb) -> Image {
+        Image { width, height, pixels: vec![background; width * height] }
+    }
+
+    #[cfg(test)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[cfg(test)]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[cfg(test)]
+    pub fn get(&self, x : usize, y : usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn fill(&mut self, x : usize, y : usize, width : usize, height : usize, color : Rgb) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                self.pixels[row * self.width + column] = color;
+            }
+        }
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        for pixel in &self.pixels {
+            out.extend_from_slice(pixel);
+        }
+        out
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        //8 bits per channel, truecolor, default compression, filter and no interlacing
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        let mut raw = Vec::with_capacity(self.height * (1 + 3 * self.width));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            //no filter
+            raw.push(0);
+            for pixel in row {
+                raw.extend_from_slice(pixel);
+            }
+        }
+
+        let mut out = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        png_chunk(&mut out, b"IHDR", &header);
+        png_chunk(&mut out, b"IDAT", &zlib_stored(&raw));
+        png_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+}
+
+fn png_chunk(out : &mut Vec<u8>, kind : &[u8; 4], data : &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+//a zlib stream made of uncompressed deflate blocks, which every decoder has to accept
+fn zlib_stored(data : &[u8]) -> Vec<u8> {
+    const MAX_BLOCK : usize = 0xffff;
+    let mut out = vec![0x78, 0x01];
+    let mut blocks : Vec<&[u8]> = data.chunks(MAX_BLOCK).collect();
+    if blocks.is_empty() {
+        blocks.push(&[]);
+    }
+    let last = blocks.len() - 1;
+    for (i, block) in blocks.into_iter().enumerate() {
+        //the first bit marks the final block, the next two being zero means stored
+        out.push(if i == last { 1 } else { 0 });
+        out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+//built at compile time, const fns can't use iterators so these are plain loops
+const CRC_TABLE : [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            bit += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(data : &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8))
+}
+
+fn adler32(data : &[u8]) -> u32 {
+    const MODULO : u32 = 65521;
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % MODULO;
+        (a, (b + a) % MODULO)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(b""), 1);
+    }
+
+    #[test]
+    fn stored_blocks() {
+        assert_eq!(zlib_stored(b"abc"), vec![0x78, 0x01, 1, 3, 0, 0xfc, 0xff, b'a', b'b', b'c', 0x02, 0x4d, 0x01, 0x27]);
+        assert_eq!(zlib_stored(b""), vec![0x78, 0x01, 1, 0, 0, 0xff, 0xff, 0, 0, 0, 1]);
+
+        let data = vec![7u8; 0xffff + 10];
+        let stream = zlib_stored(&data);
+        assert_eq!(&stream[2..7], &[0, 0xff, 0xff, 0, 0]);
+        let second = 7 + 0xffff;
+        assert_eq!(&stream[second..second + 5], &[1, 10, 0, 0xf5, 0xff]);
+        assert_eq!(stream.len(), 2 + 5 + 0xffff + 5 + 10 + 4);
+    }
+
+    #[test]
+    fn ppm_output() {
+        let mut image = Image::new(2, 1, [255, 255, 255]);
+        image.fill(1, 0, 5, 5, [1, 2, 3]);
+        assert_eq!(image.get(0, 0), [255, 255, 255]);
+        assert_eq!(image.get(1, 0), [1, 2, 3]);
+        assert_eq!(image.to_ppm(), b"P6\n2 1\n255\n\xff\xff\xff\x01\x02\x03".to_vec());
+    }
+
+    #[test]
+    fn png_output() {
+        let image = Image::new(1, 1, [255, 0, 0]);
+        let png = image.to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[8..16], b"\0\0\0\x0dIHDR");
+        assert_eq!(&png[16..29], &[0, 0, 0, 1, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+        assert_eq!(&png[29..33], &crc32(&png[12..29]).to_be_bytes());
+        assert_eq!(&png[33..41], b"\0\0\0\x0fIDAT");
+        assert_eq!(&png[41..56], &zlib_stored(&[0, 255, 0, 0])[..]);
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    }
+}
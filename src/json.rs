@@ -0,0 +1,266 @@
+use std::fmt::Write;
+use std::{error::Error, fmt};
+
+//quotes and escapes a string as a JSON string literal
+pub fn string(s : &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    //keys in the order they were written
+    Object(Vec<(String, Value)>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JsonParseError {
+    position : usize,
+    message : String,
+}
+
+impl Error for JsonParseError {}
+impl fmt::Display for JsonParseError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse JSON at byte {}: {}", self.position, self.message)
+    }
+}
+
+impl Value {
+    #[cfg(test)]
+    pub fn get(&self, key : &str) -> Option<&Value> {
+        match self {
+            Value::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    input : &'a str,
+    position : usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error<T>(&self, message : &str) -> Result<T, JsonParseError> {
+        Err(JsonParseError { position: self.position, message: message.to_string() })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\n' | '\r') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, expected : char) -> Result<(), JsonParseError> {
+        if self.peek() != Some(expected) {
+            return self.error(&format!("expected {:?}", expected));
+        }
+        self.position += expected.len_utf8();
+        Ok(())
+    }
+
+    fn literal(&mut self, word : &str, value : Value) -> Result<Value, JsonParseError> {
+        if self.input[self.position..].starts_with(word) {
+            self.position += word.len();
+            Ok(value)
+        } else {
+            self.error("expected a value")
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, JsonParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.literal("null", Value::Null),
+            Some('t') => self.literal("true", Value::Bool(true)),
+            Some('f') => self.literal("false", Value::Bool(false)),
+            Some('"') => self.string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            _ => self.error("expected a value"),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, JsonParseError> {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                break;
+            }
+            self.position += 1;
+        }
+        match self.input[start..self.position].parse::<f64>() {
+            Ok(number) => Ok(Value::Number(number)),
+            Err(_) => {
+                self.position = start;
+                self.error("expected a number")
+            },
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonParseError> {
+        let digits = self.input.get(self.position..self.position + 4).unwrap_or("");
+        match u32::from_str_radix(digits, 16) {
+            Ok(code) if digits.len() == 4 => {
+                self.position += 4;
+                Ok(code)
+            },
+            _ => self.error("expected four hex digits"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonParseError> {
+        self.expect('"')?;
+        let mut out = String::new();
+        loop {
+            match self.next() {
+                None => return self.error("unterminated string"),
+                Some('"') => return Ok(out),
+                Some('\\') => match self.next() {
+                    Some('"') => out.push('"'),
+                    Some('\\') => out.push('\\'),
+                    Some('/') => out.push('/'),
+                    Some('b') => out.push('\u{8}'),
+                    Some('f') => out.push('\u{c}'),
+                    Some('n') => out.push('\n'),
+                    Some('r') => out.push('\r'),
+                    Some('t') => out.push('\t'),
+                    Some('u') => {
+                        let mut code = self.hex4()?;
+                        //characters outside the basic plane come as a surrogate pair
+                        if (0xd800..0xdc00).contains(&code) && self.input[self.position..].starts_with("\\u") {
+                            self.position += 2;
+                            let low = self.hex4()?;
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff);
+                        }
+                        match char::from_u32(code) {
+                            Some(c) => out.push(c),
+                            None => return self.error("invalid unicode escape"),
+                        }
+                    },
+                    _ => return self.error("invalid escape"),
+                },
+                Some(c) if (c as u32) < 0x20 => return self.error("control character in string"),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, JsonParseError> {
+        self.expect('[')?;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.position += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some(']') => return Ok(Value::Array(values)),
+                _ => return self.error("expected ',' or ']'"),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, JsonParseError> {
+        self.expect('{')?;
+        let mut entries = vec![];
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.position += 1;
+            return Ok(Value::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.next() {
+                Some(',') => continue,
+                Some('}') => return Ok(Value::Object(entries)),
+                _ => return self.error("expected ',' or '}'"),
+            }
+        }
+    }
+}
+
+//a single JSON value, with nothing but whitespace around it
+pub fn parse(input : &str) -> Result<Value, JsonParseError> {
+    let mut parser = Parser { input, position: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.position < input.len() {
+        return parser.error("unexpected trailing characters");
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_strings() {
+        assert_eq!(string("abc"), "\"abc\"");
+        assert_eq!(string("a\"b\\c\nd\u{1}"), "\"a\\\"b\\\\c\\nd\\u0001\"");
+        assert_eq!(string("é"), "\"é\"");
+    }
+
+    #[test]
+    fn parse_values() {
+        let value = parse(r#" {"a": [1, -2.5e1, true, null], "b": "x\"\u00e9\ud83d\ude00", "c": {}} "#).unwrap();
+        assert_eq!(value, Value::Object(vec![
+            ("a".to_string(), Value::Array(vec![Value::Number(1.0), Value::Number(-25.0), Value::Bool(true), Value::Null])),
+            ("b".to_string(), Value::String("x\"é😀".to_string())),
+            ("c".to_string(), Value::Object(vec![])),
+        ]));
+        assert_eq!(value.get("c"), Some(&Value::Object(vec![])));
+        assert_eq!(parse(string("a\"b\\c\nd\u{1}").as_str()), Ok(Value::String("a\"b\\c\nd\u{1}".to_string())));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse("[1, 2").unwrap_err().to_string(), "Unable to parse JSON at byte 5: expected ',' or ']'");
+        assert_eq!(parse("{\"a\" 1}").unwrap_err().to_string(), "Unable to parse JSON at byte 5: expected ':'");
+        assert_eq!(parse("\"abc").unwrap_err().to_string(), "Unable to parse JSON at byte 4: unterminated string");
+        assert_eq!(parse("nul").unwrap_err().to_string(), "Unable to parse JSON at byte 0: expected a value");
+        assert_eq!(parse("1 2").unwrap_err().to_string(), "Unable to parse JSON at byte 2: unexpected trailing characters");
+    }
+}
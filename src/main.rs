@@ -1,4 +1,12 @@
+mod bitgrid;
+mod csv;
+mod geometry;
+mod grid;
+mod image;
 mod inputs;
+mod json;
+mod options;
+mod siphash;
 
 mod day1;
 mod day2;
@@ -12,23 +20,30 @@ mod day9;
 
 extern crate paste;
 
+use options::Options;
+
 macro_rules! run_day {
-    ($input:ident) => {
-        println!("{} start {} {}", "=".repeat(10), stringify!($input), "=".repeat(10));
-        $input::main()?;
-        println!("{} end {} {}", "=".repeat(10), stringify!($input), "=".repeat(10));
+    ($options:ident, $input:ident) => {
+        if $options.runs_day(stringify!($input)) {
+            println!("{} start {} {}", "=".repeat(10), stringify!($input), "=".repeat(10));
+            $input::main()?;
+            println!("{} end {} {}", "=".repeat(10), stringify!($input), "=".repeat(10));
+        }
     }
 }
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    run_day!(day1);
-    run_day!(day2);
-    run_day!(day3);
-    run_day!(day4);
-    run_day!(day5);
-    run_day!(day6);
-    run_day!(day7);
-    run_day!(day8);
-    run_day!(day9);
+    let options = Options::from_env();
+    options.check_shared_keys()?;
+
+    run_day!(options, day1);
+    run_day!(options, day2);
+    run_day!(options, day3);
+    run_day!(options, day4);
+    run_day!(options, day5);
+    run_day!(options, day6);
+    run_day!(options, day7);
+    run_day!(options, day8);
+    run_day!(options, day9);
 
     Ok(())
 }
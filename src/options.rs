@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::{error::Error, fmt};
+
+//command line options of the form `--key value...` or `--flag`
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Options {
+    values : HashMap<String, Vec<String>>,
+}
+
+#[derive(Debug)]
+pub struct OptionsError {
+    key : String,
+    value : String,
+}
+
+impl Error for OptionsError {}
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to parse option --{}: {:?}", self.key, self.value)
+    }
+}
+
+#[derive(Debug)]
+pub struct SharedOptionError {
+    key : String,
+}
+
+impl Error for SharedOptionError {}
+impl fmt::Display for SharedOptionError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unable to use option --{} without --day, more than one day reads it", self.key)
+    }
+}
+
+//keys that mean different things to different days, such as day2's and day4's `--input`
+const SHARED_KEYS : [&str; 5] = ["from", "input", "lenient", "output", "report"];
+
+impl Options {
+    pub fn from_env() -> Options {
+        Options::from_args(std::env::args().skip(1))
+    }
+
+    pub fn from_args<I : IntoIterator<Item = String>>(args : I) -> Options {
+        let mut values : HashMap<String, Vec<String>> = HashMap::new();
+        let mut current : Option<String> = None;
+        for arg in args {
+            if let Some(key) = arg.strip_prefix("--") {
+                values.entry(key.to_string()).or_default();
+                current = Some(key.to_string());
+            } else if let Some(key) = &current {
+                values.entry(key.clone()).or_default().push(arg);
+            }
+        }
+        Options { values }
+    }
+
+    //true for `--flag`, with or without values
+    pub fn has(&self, key : &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub fn get(&self, key : &str) -> Option<&str> {
+        self.values.get(key)
+            .and_then(|values| values.last())
+            .map(String::as_str)
+    }
+
+    //every value given for a key, in order, so `--slopes 1,1 3,1` yields both slopes
+    pub fn get_all(&self, key : &str) -> Vec<&str> {
+        self.values.get(key)
+            .map(|values| values.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn parse<T : FromStr>(&self, key : &str) -> Result<Option<T>, OptionsError> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(value) => value.parse::<T>()
+                .map(Some)
+                .map_err(|_| OptionsError { key: key.to_string(), value: value.to_string() }),
+        }
+    }
+
+    //shared keys would reach every day that runs, so they need `--day`
+    pub fn check_shared_keys(&self) -> Result<(), SharedOptionError> {
+        match SHARED_KEYS.iter().find(|key| self.has(key)) {
+            Some(key) if !self.has("day") => Err(SharedOptionError { key: key.to_string() }),
+            _ => Ok(()),
+        }
+    }
+
+    //`--day 3` or `--day day3` restricts the run to a single day
+    pub fn runs_day(&self, day : &str) -> bool {
+        match self.get("day") {
+            None => true,
+            Some(selected) => selected == day || selected == day.trim_start_matches("day"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args : &str) -> Options {
+        Options::from_args(args.split_whitespace().map(str::to_string))
+    }
+
+    #[test]
+    fn parse_values_and_flags() {
+        let options = options("--day 3 --input a b --verbose");
+        assert_eq!(options.get("day"), Some("3"));
+        assert_eq!(options.get("input"), Some("b"));
+        assert_eq!(options.get("verbose"), None);
+        assert_eq!(options.get("missing"), None);
+        assert!(options.has("verbose"));
+        assert!(!options.has("missing"));
+        assert_eq!(options.get_all("input"), vec!["a", "b"]);
+        assert!(options.get_all("verbose").is_empty());
+        assert!(options.get_all("missing").is_empty());
+    }
+
+    #[test]
+    fn parse_typed_values() {
+        let options = options("--threads 4 --seed x");
+        assert_eq!(options.parse::<usize>("threads").unwrap(), Some(4));
+        assert_eq!(options.parse::<usize>("missing").unwrap(), None);
+        assert_eq!(options.parse::<u64>("seed").unwrap_err().to_string(), "Unable to parse option --seed: \"x\"");
+    }
+
+    #[test]
+    fn select_day() {
+        assert!(options("").runs_day("day2"));
+        assert!(options("--day 2").runs_day("day2"));
+        assert!(options("--day day2").runs_day("day2"));
+        assert!(!options("--day 2").runs_day("day3"));
+    }
+
+    #[test]
+    fn shared_keys_need_a_day() {
+        assert!(options("--seed 4").check_shared_keys().is_ok());
+        assert!(options("--day 4 --input a --lenient").check_shared_keys().is_ok());
+        assert_eq!(options("--input a").check_shared_keys().unwrap_err().to_string(),
+            "Unable to use option --input without --day, more than one day reads it");
+        assert!(options("--lenient").check_shared_keys().is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//SipHash-2-4, a keyed hash for short inputs
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SipKey {
+    k0 : u64,
+    k1 : u64,
+}
+
+impl SipKey {
+    #[cfg(test)]
+    pub fn new(bytes : [u8; 16]) -> SipKey {
+        let mut k0 = [0u8; 8];
+        let mut k1 = [0u8; 8];
+        k0.copy_from_slice(&bytes[..8]);
+        k1.copy_from_slice(&bytes[8..]);
+        SipKey { k0: u64::from_le_bytes(k0), k1: u64::from_le_bytes(k1) }
+    }
+
+    //a key from a passphrase, so people can share a word instead of sixteen bytes
+    pub fn from_passphrase(passphrase : &str) -> SipKey {
+        let zero = SipKey { k0: 0, k1: 0 };
+        let one = SipKey { k0: 1, k1: 0 };
+        SipKey { k0: zero.hash(passphrase.as_bytes()), k1: one.hash(passphrase.as_bytes()) }
+    }
+
+    pub fn hash(&self, data : &[u8]) -> u64 {
+        let mut v = [
+            self.k0 ^ 0x736f6d6570736575,
+            self.k1 ^ 0x646f72616e646f6d,
+            self.k0 ^ 0x6c7967656e657261,
+            self.k1 ^ 0x7465646279746573,
+        ];
+        let chunks = data.chunks_exact(8);
+        let rest = chunks.remainder();
+        for chunk in chunks {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            let m = u64::from_le_bytes(word);
+            v[3] ^= m;
+            round(&mut v);
+            round(&mut v);
+            v[0] ^= m;
+        }
+        //the last word holds the leftover bytes and the length
+        let mut last = (data.len() as u64 & 0xff) << 56;
+        for (i, byte) in rest.iter().enumerate() {
+            last |= (*byte as u64) << (8 * i);
+        }
+        v[3] ^= last;
+        round(&mut v);
+        round(&mut v);
+        v[0] ^= last;
+        v[2] ^= 0xff;
+        for _ in 0..4 {
+            round(&mut v);
+        }
+        v[0] ^ v[1] ^ v[2] ^ v[3]
+    }
+}
+
+fn round(v : &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_vectors() {
+        let mut bytes = [0u8; 16];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let key = SipKey::new(bytes);
+        let message : Vec<u8> = (0..15).collect();
+        assert_eq!(key.hash(&[]), 0x726fdb47dd0e0e31);
+        assert_eq!(key.hash(&message[..1]), 0x74f839c593dc67fd);
+        assert_eq!(key.hash(&message[..8]), 0x93f5f5799a932462);
+        assert_eq!(key.hash(&message), 0xa129ca6149be45e5);
+    }
+
+    #[test]
+    fn passphrase_keys() {
+        assert_eq!(SipKey::from_passphrase("north pole"), SipKey::from_passphrase("north pole"));
+        assert_ne!(SipKey::from_passphrase("north pole"), SipKey::from_passphrase("south pole"));
+    }
+}